@@ -0,0 +1,316 @@
+use std::collections::HashSet;
+use super::matrix::Matrix;
+
+const WORD_BITS: usize = 64;
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct BitGrid {
+    words: Vec<u64>,
+    n_rows: usize,
+    n_columns: usize,
+    words_per_row: usize
+}
+
+impl BitGrid {
+    pub fn new(n_rows: usize, n_columns: usize) -> BitGrid {
+        let words_per_row = n_columns.div_ceil(WORD_BITS);
+        return BitGrid { words: vec![0; n_rows * words_per_row], n_rows, n_columns, words_per_row }
+    }
+
+    pub fn from_matrix(matrix: &Matrix<bool>) -> BitGrid {
+        let mut grid = BitGrid::new(matrix.n_rows(), matrix.n_columns());
+        for (x, y) in matrix.positions_where(|v| *v) {
+            grid.set(x as usize, y as usize)
+        }
+        return grid
+    }
+
+    pub fn to_matrix(&self) -> Matrix<bool> {
+        return Matrix::from_rows((0..self.n_rows).map(|y|
+            (0..self.n_columns).map(|x| self.test(x, y)).collect()
+        ).collect())
+    }
+
+    pub fn from_dots(dots: &HashSet<(u32, u32)>) -> BitGrid {
+        let n_columns = dots.iter().map(|d| d.0 as usize + 1).max().unwrap_or(0);
+        let n_rows = dots.iter().map(|d| d.1 as usize + 1).max().unwrap_or(0);
+        let mut grid = BitGrid::new(n_rows, n_columns);
+        for &(x, y) in dots {
+            grid.set(x as usize, y as usize)
+        }
+        return grid
+    }
+
+    pub fn to_dots(&self) -> HashSet<(u32, u32)> {
+        let mut dots = HashSet::new();
+        for y in 0..self.n_rows {
+            for (word_id, word) in self._row(y).iter().enumerate() {
+                let mut bits = *word;
+                while bits != 0 {
+                    let bit = bits.trailing_zeros() as usize;
+                    dots.insert(((word_id * WORD_BITS + bit) as u32, y as u32));
+                    bits &= bits - 1;
+                }
+            }
+        }
+        return dots
+    }
+
+    pub fn n_rows(&self) -> usize {
+        return self.n_rows
+    }
+
+    pub fn n_columns(&self) -> usize {
+        return self.n_columns
+    }
+
+    pub fn set(&mut self, x: usize, y: usize) {
+        let (idx, mask) = self._locate(x, y);
+        self.words[idx] |= mask
+    }
+
+    pub fn clear(&mut self, x: usize, y: usize) {
+        let (idx, mask) = self._locate(x, y);
+        self.words[idx] &= !mask
+    }
+
+    pub fn test(&self, x: usize, y: usize) -> bool {
+        let (idx, mask) = self._locate(x, y);
+        return self.words[idx] & mask != 0
+    }
+
+    pub fn count_ones(&self) -> usize {
+        return self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn and(&self, other: &BitGrid) -> BitGrid {
+        return self._combine(other, |a, b| a & b)
+    }
+
+    pub fn or(&self, other: &BitGrid) -> BitGrid {
+        return self._combine(other, |a, b| a | b)
+    }
+
+    pub fn xor(&self, other: &BitGrid) -> BitGrid {
+        return self._combine(other, |a, b| a ^ b)
+    }
+
+    // Positive offsets move cells towards higher x, cells moved outside the grid are dropped
+    pub fn shift_columns(&self, by: i64) -> BitGrid {
+        let mut shifted = BitGrid::new(self.n_rows, self.n_columns);
+        for y in 0..self.n_rows {
+            let row = BitGrid::_shift_words(self._row(y), by);
+            shifted._row_mut(y).copy_from_slice(&row);
+            shifted._mask_tail(y);
+        }
+        return shifted
+    }
+
+    // Positive offsets move cells towards higher y, cells moved outside the grid are dropped
+    pub fn shift_rows(&self, by: i64) -> BitGrid {
+        let mut shifted = BitGrid::new(self.n_rows, self.n_columns);
+        for y in 0..self.n_rows {
+            let source_y = y as i64 - by;
+            if source_y < 0 || source_y >= self.n_rows as i64 { continue }
+            shifted._row_mut(y).copy_from_slice(self._row(source_y as usize));
+        }
+        return shifted
+    }
+
+    pub fn mirror_horizontal(&self) -> BitGrid {
+        let mut mirrored = BitGrid::new(self.n_rows, self.n_columns);
+        let padding = (self.words_per_row * WORD_BITS - self.n_columns) as i64;
+        for y in 0..self.n_rows {
+            let reversed: Vec<u64> = self._row(y).iter().rev().map(|w| w.reverse_bits()).collect();
+            let row = BitGrid::_shift_words(&reversed, -padding);
+            mirrored._row_mut(y).copy_from_slice(&row);
+        }
+        return mirrored
+    }
+
+    pub fn mirror_vertical(&self) -> BitGrid {
+        let mut mirrored = BitGrid::new(self.n_rows, self.n_columns);
+        for y in 0..self.n_rows {
+            mirrored._row_mut(self.n_rows - 1 - y).copy_from_slice(self._row(y));
+        }
+        return mirrored
+    }
+
+    // Folds the part right of column `along` onto the left part, the result has `along` columns
+    pub fn fold_along_x(&self, along: usize) -> BitGrid {
+        let offset = 2 * along as i64 + 1 - self.n_columns as i64;
+        let folded = self.or(&self.mirror_horizontal().shift_columns(offset));
+        return folded._resized(self.n_rows, along)
+    }
+
+    // Folds the part below row `along` onto the upper part, the result has `along` rows
+    pub fn fold_along_y(&self, along: usize) -> BitGrid {
+        let offset = 2 * along as i64 + 1 - self.n_rows as i64;
+        let folded = self.or(&self.mirror_vertical().shift_rows(offset));
+        return folded._resized(along, self.n_columns)
+    }
+
+    fn _locate(&self, x: usize, y: usize) -> (usize, u64) {
+        if x >= self.n_columns || y >= self.n_rows {
+            panic!("({}, {}) is outside of {}x{} grid", x, y, self.n_columns, self.n_rows)
+        }
+        return (y * self.words_per_row + x / WORD_BITS, 1u64 << (x % WORD_BITS))
+    }
+
+    fn _row(&self, y: usize) -> &[u64] {
+        return &self.words[(y * self.words_per_row)..((y + 1) * self.words_per_row)]
+    }
+
+    fn _row_mut(&mut self, y: usize) -> &mut [u64] {
+        return &mut self.words[(y * self.words_per_row)..((y + 1) * self.words_per_row)]
+    }
+
+    fn _mask_tail(&mut self, y: usize) {
+        let used_bits = self.n_columns % WORD_BITS;
+        if used_bits == 0 { return }
+        let last = (y + 1) * self.words_per_row - 1;
+        self.words[last] &= (1u64 << used_bits) - 1
+    }
+
+    fn _combine<F>(&self, other: &BitGrid, op: F) -> BitGrid where F: Fn(u64, u64) -> u64 {
+        if self.n_rows != other.n_rows || self.n_columns != other.n_columns {
+            panic!(
+                "Dimensions differ: {}x{} vs {}x{}",
+                self.n_columns, self.n_rows, other.n_columns, other.n_rows
+            )
+        }
+        let words = self.words.iter().zip(other.words.iter()).map(|(a, b)| op(*a, *b)).collect();
+        return BitGrid { words, ..*self }
+    }
+
+    fn _resized(&self, n_rows: usize, n_columns: usize) -> BitGrid {
+        let mut resized = BitGrid::new(n_rows, n_columns);
+        let words_to_copy = resized.words_per_row.min(self.words_per_row);
+        for y in 0..n_rows.min(self.n_rows) {
+            let source = &self._row(y)[..words_to_copy];
+            resized._row_mut(y)[..words_to_copy].copy_from_slice(source);
+            resized._mask_tail(y);
+        }
+        return resized
+    }
+
+    fn _shift_words(words: &[u64], by: i64) -> Vec<u64> {
+        let n_words = words.len() as i64;
+        let word_shift = by.abs() / WORD_BITS as i64;
+        let bit_shift = (by.abs() % WORD_BITS as i64) as u32;
+        let word_at = |idx: i64| if idx >= 0 && idx < n_words { words[idx as usize] } else { 0 };
+
+        return (0..n_words).map(|i| {
+            if by >= 0 {
+                let low = word_at(i - word_shift) << bit_shift;
+                let carry = if bit_shift == 0 { 0 } else { word_at(i - word_shift - 1) >> (64 - bit_shift) };
+                low | carry
+            } else {
+                let high = word_at(i + word_shift) >> bit_shift;
+                let carry = if bit_shift == 0 { 0 } else { word_at(i + word_shift + 1) << (64 - bit_shift) };
+                high | carry
+            }
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exs::utils::strs_to_strings;
+
+    fn render(grid: &BitGrid) -> Vec<String> {
+        return grid.to_matrix().render_chars(|v| if *v { '#' } else { '.' }, None)
+    }
+
+    fn from_rendered(rows: &[&str]) -> BitGrid {
+        let lines = strs_to_strings(rows).iter()
+            .map(|r| r.chars().map(|c| if c == '#' { "1" } else { "0" }).collect::<String>())
+            .collect();
+        let matrix = Matrix::<u8>::from_lines(&lines).map(|v| *v == 1);
+        return BitGrid::from_matrix(&matrix)
+    }
+
+    #[test]
+    fn test_set_clear_test() {
+        let mut grid = BitGrid::new(3, 130);
+        grid.set(0, 0);
+        grid.set(129, 2);
+        grid.set(64, 1);
+        assert!(grid.test(129, 2));
+        assert!(!grid.test(128, 2));
+        assert_eq!(grid.count_ones(), 3);
+        grid.clear(64, 1);
+        assert!(!grid.test(64, 1));
+        assert_eq!(grid.count_ones(), 2);
+        assert_eq!(grid.to_dots(), vec![(0, 0), (129, 2)].into_iter().collect());
+    }
+
+    #[test]
+    fn test_bitwise_operations() {
+        let a = from_rendered(&["##..", "#.#."]);
+        let b = from_rendered(&[".#.#", "#..#"]);
+        assert_eq!(render(&a.and(&b)), strs_to_strings(&[".#..", "#..."]));
+        assert_eq!(render(&a.or(&b)), strs_to_strings(&["##.#", "#.##"]));
+        assert_eq!(render(&a.xor(&b)), strs_to_strings(&["#..#", "..##"]));
+    }
+
+    #[test]
+    fn test_shift_and_mirror() {
+        let grid = from_rendered(&["##..#", "..#.."]);
+        assert_eq!(render(&grid.shift_columns(1)), strs_to_strings(&[".##..", "...#."]));
+        assert_eq!(render(&grid.shift_columns(-2)), strs_to_strings(&["..#..", "#...."]));
+        assert_eq!(render(&grid.shift_rows(1)), strs_to_strings(&[".....", "##..#"]));
+        assert_eq!(render(&grid.mirror_horizontal()), strs_to_strings(&["#..##", "..#.."]));
+        assert_eq!(render(&grid.mirror_vertical()), strs_to_strings(&["..#..", "##..#"]));
+
+        let mut wide = BitGrid::new(1, 200);
+        wide.set(3, 0);
+        wide.set(70, 0);
+        assert_eq!(wide.mirror_horizontal().to_dots(), vec![(196, 0), (129, 0)].into_iter().collect());
+        assert_eq!(wide.shift_columns(127).to_dots(), vec![(130, 0), (197, 0)].into_iter().collect());
+        assert_eq!(wide.shift_columns(-65).to_dots(), vec![(5, 0)].into_iter().collect());
+    }
+
+    #[test]
+    fn test_folds_match_paper_example() {
+        let dots: HashSet<(u32, u32)> = vec![
+            (6, 10), (0, 14), (9, 10), (0, 3), (10, 4), (4, 11), (6, 0), (6, 12), (4, 1),
+            (0, 13), (10, 12), (3, 4), (3, 0), (8, 4), (1, 10), (2, 14), (8, 10), (9, 0)
+        ].into_iter().collect();
+        let grid = BitGrid::from_dots(&dots);
+        assert_eq!(grid.to_matrix().n_rows(), 15);
+        assert_eq!(BitGrid::from_matrix(&grid.to_matrix()), grid);
+
+        let folded_by_y = grid.fold_along_y(7);
+        assert_eq!(folded_by_y.count_ones(), 17);
+        let folded_by_x = folded_by_y.fold_along_x(5);
+        assert_eq!(folded_by_x.count_ones(), 16);
+        assert_eq!(render(&folded_by_x), strs_to_strings(&[
+            "#####",
+            "#...#",
+            "#...#",
+            "#...#",
+            "#####",
+            ".....",
+            "....."
+        ]));
+    }
+
+    #[test]
+    fn test_large_grid() {
+        let side = 8_000;
+        let mut grid = BitGrid::new(side, side);
+        for i in 0..side {
+            grid.set(i, i);
+            grid.set(side - 1 - i, i);
+        }
+        assert_eq!(grid.count_ones(), 2 * side);
+        let folded = grid.fold_along_x(side / 2).fold_along_y(side / 2);
+        assert_eq!(folded.n_columns(), side / 2);
+        assert_eq!(folded.n_rows(), side / 2);
+        assert!(folded.test(0, 0));
+        assert!(folded.test(side / 2 - 1, side / 2 - 1));
+        assert_eq!(grid.xor(&grid.mirror_horizontal()).count_ones(), 0);
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::VecDeque;
+use super::matrix::{Matrix, DIAGONAL_OFFSETS, ORTHOGONAL_OFFSETS};
+use super::sparse_grid::BoundingBox;
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Component {
+    pub label: usize,
+    pub cells: Vec<(i32, i32)>,
+    pub bounding_box: BoundingBox
+}
+
+impl Component {
+    pub fn size(&self) -> usize {
+        return self.cells.len()
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Labelling {
+    pub labels: Matrix<Option<usize>>,
+    pub components: Vec<Component>
+}
+
+impl Labelling {
+    pub fn sizes(&self) -> Vec<usize> {
+        return self.components.iter().map(|c| c.size()).collect()
+    }
+}
+
+impl<T: Clone> Matrix<T> {
+    pub fn flood_fill<P>(&self, x: i32, y: i32, with_diagonals: bool, predicate: P) -> Vec<(i32, i32)>
+        where P: Fn(&T) -> bool {
+        let mut visited = vec![false; self.n_rows() * self.n_columns()];
+        return self._flood_fill(x, y, with_diagonals, &predicate, &mut visited)
+    }
+
+    pub fn label_components<P>(&self, with_diagonals: bool, predicate: P) -> Labelling
+        where P: Fn(&T) -> bool {
+        let mut visited = vec![false; self.n_rows() * self.n_columns()];
+        let mut labels = Matrix::filled(self.n_rows(), self.n_columns(), None);
+        let mut components = Vec::new();
+
+        for y in 0..(self.n_rows() as i32) {
+            for x in 0..(self.n_columns() as i32) {
+                if visited[self._flat_index(x, y)] { continue }
+                let cells = self._flood_fill(x, y, with_diagonals, &predicate, &mut visited);
+                if cells.is_empty() { continue }
+
+                let label = components.len();
+                let (x_0, y_0) = cells[0];
+                let mut bounding_box = BoundingBox { min_x: x_0, min_y: y_0, max_x: x_0, max_y: y_0 };
+                for &(x_c, y_c) in &cells {
+                    *labels.get_mut_point(x_c, y_c).unwrap() = Some(label);
+                    bounding_box.min_x = bounding_box.min_x.min(x_c);
+                    bounding_box.min_y = bounding_box.min_y.min(y_c);
+                    bounding_box.max_x = bounding_box.max_x.max(x_c);
+                    bounding_box.max_y = bounding_box.max_y.max(y_c);
+                }
+                components.push(Component { label, cells, bounding_box })
+            }
+        }
+        return Labelling { labels, components }
+    }
+
+    fn _flat_index(&self, x: i32, y: i32) -> usize {
+        return y as usize * self.n_columns() + x as usize
+    }
+
+    fn _flood_fill<P>(
+        &self,
+        x: i32,
+        y: i32,
+        with_diagonals: bool,
+        predicate: &P,
+        visited: &mut [bool]
+    ) -> Vec<(i32, i32)> where P: Fn(&T) -> bool {
+        let mut cells = Vec::new();
+        if !self.index_exists(x, y) || visited[self._flat_index(x, y)] { return cells }
+        if !predicate(&self.get_point(x, y).unwrap()) { return cells }
+
+        let offsets: Vec<(i32, i32)> = ORTHOGONAL_OFFSETS.iter()
+            .chain(DIAGONAL_OFFSETS.iter().filter(|_| with_diagonals))
+            .cloned().collect();
+
+        let mut queue = VecDeque::new();
+        visited[self._flat_index(x, y)] = true;
+        queue.push_back((x, y));
+        while let Some((x_c, y_c)) = queue.pop_front() {
+            cells.push((x_c, y_c));
+            for (dx, dy) in &offsets {
+                let (x_n, y_n) = (x_c + dx, y_c + dy);
+                if !self.index_exists(x_n, y_n) { continue }
+                let idx = self._flat_index(x_n, y_n);
+                if visited[idx] { continue }
+                if predicate(&self.get_point(x_n, y_n).unwrap()) {
+                    visited[idx] = true;
+                    queue.push_back((x_n, y_n));
+                }
+            }
+        }
+        return cells
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exs::utils::strs_to_strings;
+
+    #[test]
+    fn test_flood_fill() {
+        let m = Matrix::<u32>::from_lines(&strs_to_strings(&vec![
+            "1191",
+            "1919",
+            "9111"
+        ]));
+        let mut cells = m.flood_fill(0, 0, false, |h| *h != 9);
+        cells.sort();
+        assert_eq!(cells, vec![(0, 0), (0, 1), (1, 0)]);
+        assert_eq!(m.flood_fill(2, 1, true, |h| *h != 9).len(), 8);
+        assert_eq!(m.flood_fill(1, 1, true, |h| *h != 9), vec![]);
+        assert_eq!(m.flood_fill(2, 0, false, |h| *h != 9), vec![]);
+        assert_eq!(m.flood_fill(-1, 0, false, |h| *h != 9), vec![]);
+    }
+
+    #[test]
+    fn test_label_components() {
+        let m = Matrix::<u32>::from_lines(&strs_to_strings(&vec![
+            "1191",
+            "1919",
+            "9111"
+        ]));
+        let labelling = m.label_components(false, |h| *h != 9);
+        assert_eq!(labelling.sizes(), vec![3, 1, 4]);
+        assert_eq!(labelling.labels.get_point(0, 1), Some(Some(0)));
+        assert_eq!(labelling.labels.get_point(2, 0), Some(None));
+        assert_eq!(labelling.labels.get_point(3, 0), Some(Some(1)));
+        assert_eq!(labelling.labels.get_point(3, 2), Some(Some(2)));
+        assert_eq!(
+            labelling.components[2].bounding_box,
+            BoundingBox { min_x: 1, min_y: 1, max_x: 3, max_y: 2 }
+        );
+
+        let with_diagonals = m.label_components(true, |h| *h != 9);
+        assert_eq!(with_diagonals.sizes(), vec![8]);
+    }
+
+    #[test]
+    fn test_label_components_on_large_grid() {
+        let side = 1500;
+        let m = Matrix::filled(side, side, 1u8);
+        let labelling = m.label_components(false, |v| *v == 1);
+        assert_eq!(labelling.sizes(), vec![side * side]);
+
+        let snake = Matrix::from_rows((0..side).map(|y|
+            (0..side).map(|x|
+                if y % 2 == 0 { true }
+                else if y % 4 == 1 { x == side - 1 }
+                else { x == 0 }
+            ).collect()
+        ).collect());
+        let labelling = snake.label_components(false, |v| *v);
+        assert_eq!(labelling.components.len(), 1);
+    }
+}
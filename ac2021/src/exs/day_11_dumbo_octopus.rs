@@ -1,4 +1,6 @@
 use crate::exs::matrix::Matrix;
+use crate::exs::matrix_io::MatrixFormat;
+use crate::exs::point::Point2;
 use super::utils::read_lines_as_str_vector;
 
 #[derive(Debug, Eq, PartialEq)]
@@ -14,47 +16,26 @@ impl Cavern {
     const MAX_ITER: u32 = 100;
 
     fn new(rows: &Vec<String>) -> Cavern {
-        let m = Matrix::<u32>::from_lines(rows);
+        return Cavern::from_energies(Matrix::<u32>::from_lines(rows))
+    }
+
+    fn from_energies(energies: Matrix<u32>) -> Cavern {
         return Cavern {
-            energies: m.clone(),
-            n_rows: m.n_rows(),
-            n_columns: m.n_columns(),
+            n_rows: energies.n_rows(),
+            n_columns: energies.n_columns(),
+            energies,
             flashes: 0
         }
     }
 
-    fn neighbour_energies_indexes(&self, target_x: usize, target_y: usize) -> Vec<(usize, usize)> {
-        let x_ = target_x as i32;
-        let y_ = target_y as i32;
-        let indexes_to_find = [
-            (x_ + 1, y_),
-            (x_ - 1, y_),
-            (x_, y_ + 1),
-            (x_, y_ - 1),
-
-            (x_ + 1, y_ + 1),
-            (x_ + 1, y_ - 1),
-            (x_ - 1, y_ + 1),
-            (x_ - 1, y_ - 1)
-        ];
-
-        let mut neighbours = Vec::new();
-        for pair in indexes_to_find {
-            if self.energies.index_exists(pair.0, pair.1) {
-                neighbours.push((pair.0 as usize, pair.1 as usize))
-            }
-        }
-
-        return neighbours
+    fn neighbour_positions(&self, target: Point2) -> Vec<Point2> {
+        return self.energies.neighbours(target.x, target.y, true).into_iter()
+            .map(|(position, _)| Point2::from(position))
+            .collect()
     }
 
     fn trigger(&mut self) -> u64 {
-        for x in 0..(self.n_columns as i32) {
-            for y in 0..(self.n_rows as i32) {
-                let energy = self.energies.get_mut_point(x, y).unwrap();
-                *energy += 1
-            }
-        }
+        self.energies.map_in_place(|energy| *energy += 1);
 
         let mut flashes = 0;
         let mut iter_id: u32 = 0;
@@ -67,16 +48,14 @@ impl Cavern {
 
             for y in 0..self.n_rows {
                 for x in 0..self.n_columns {
-                    let energy = self.energies.get_mut_point(x as i32, y as i32).unwrap();
+                    let position = Point2::from((x, y));
+                    let energy = self.energies.get_mut(position).unwrap();
 
                     if *energy > Cavern::MAX_ENERGY {
                         flashes += 1;
                         *energy = 0;
-                        let neighbour_coordinates =
-                            Cavern::neighbour_energies_indexes(self, x, y);
-                        for (x_n, y_n) in neighbour_coordinates {
-                            let neighbour_energy =
-                                self.energies.get_mut_point(x_n as i32, y_n as i32).unwrap();
+                        for neighbour in Cavern::neighbour_positions(self, position) {
+                            let neighbour_energy = self.energies.get_mut(neighbour).unwrap();
                             if *neighbour_energy > 0 {
                                 *neighbour_energy += 1
                             }
@@ -90,10 +69,7 @@ impl Cavern {
     }
 
     fn has_max_energies(&self) -> bool {
-        for energy in self.energies.points_iter() {
-            if energy > Cavern::MAX_ENERGY { return true }
-        }
-        return false
+        return self.energies.count_where(|energy| *energy > Cavern::MAX_ENERGY) > 0
     }
 }
 
@@ -127,6 +103,31 @@ pub fn day_11() {
     println!("Day 11 Part 2 result: {res}", res=result);
 }
 
+// Usage: octopus <grid-file> <steps> [snapshot-file] [digits|csv|binary], runs the
+// octopus grid for the given number of steps and prints the flashes. The grid is
+// read in the given format (digits by default) and the energies after the last
+// step are saved to the snapshot file in that format, so a later run resumes from it.
+pub fn octopus_command(args: &[String]) {
+    let usage = "Usage: octopus <grid-file> <steps> [snapshot-file] [digits|csv|binary]";
+    let (filename, n_steps) = match (args.first(), args.get(1)) {
+        (Some(filename), Some(steps)) => (filename, steps.parse::<u32>().expect(usage)),
+        _ => panic!("{}", usage)
+    };
+    let format = match args.get(3) {
+        Some(encoded) => encoded.parse::<MatrixFormat>().unwrap_or_else(|e| panic!("{}", e)),
+        None => MatrixFormat::Digits
+    };
+    let energies = Matrix::<u32>::load(filename, &format)
+        .unwrap_or_else(|e| panic!("Invalid octopus grid in {}: {}", filename, e));
+    let mut cavern = Cavern::from_energies(energies);
+    let flashes: u64 = (0..n_steps).map(|_| cavern.trigger()).sum();
+    println!("Flashes after {} steps: {}", n_steps, flashes);
+    if let Some(snapshot) = args.get(2) {
+        cavern.energies.save(snapshot, &format).unwrap_or_else(|e| panic!("{}", e));
+        println!("Wrote the energies after {} steps to {}", n_steps, snapshot)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::exs::utils::strs_to_strings;
@@ -180,4 +181,29 @@ mod tests {
 
         assert_eq!(create_cavern_and_find_first_sync(&input_data, 200), 195)
     }
+
+    #[test]
+    fn test_resume_from_snapshot() {
+        let input_data = strs_to_strings(&vec![
+            "5483143223",
+            "2745854711",
+            "5264556173",
+            "6141336146",
+            "6357385478",
+            "4167524645",
+            "2176841721",
+            "6882881134",
+            "4846848554",
+            "5283751526"
+        ]);
+        let mut cavern = Cavern::new(&input_data);
+        let mut total_flashes = 0;
+        for _ in 0..50 { total_flashes += cavern.trigger() }
+
+        let snapshot = cavern.energies.to_bytes();
+        let mut resumed = Cavern::from_energies(Matrix::from_bytes(&snapshot).unwrap());
+        assert_eq!(resumed.energies, cavern.energies);
+        for _ in 50..100 { total_flashes += resumed.trigger() }
+        assert_eq!(total_flashes, 1656);
+    }
 }
@@ -1,6 +1,11 @@
-use std::collections::{HashMap, HashSet};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
 
-use super::utils::read_lines_as_str_vector;
+use super::graph::{Graph, NodeId};
+use super::render::dot_id;
+use super::utils::{json_array, json_string, read_lines_as_str_vector};
 
 #[derive(Clone, Eq, PartialEq, Hash, Debug)]
 struct Cave {
@@ -27,20 +32,76 @@ impl Cave {
 
 #[derive(Clone, Eq, PartialEq, Hash, Debug)]
 struct Connection {
-    start: Cave,
-    end: Cave
+    start: String,
+    end: String,
+    weight: u64
 }
 
 impl Connection {
     fn new(start_id: &String, end_id: &String) -> Connection {
         return Connection {
-            start: Cave::new(start_id),
-            end: Cave::new(end_id)
+            start: start_id.clone(),
+            end: end_id.clone(),
+            weight: 1
         }
     }
 
+    fn with_weight(mut self, weight: u64) -> Connection {
+        self.weight = weight;
+        return self
+    }
+
     fn repr(&self) -> String {
-        return format!("Connection({} -> {})", self.start.id, self.end.id)
+        return format!("Connection({} -> {})", self.start, self.end)
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+struct Visits {
+    counts: Vec<u32>,
+    small_cave_revisits: u32
+}
+
+impl Visits {
+    fn new(n_caves: usize) -> Visits {
+        return Visits { counts: vec![0; n_caves], small_cave_revisits: 0 }
+    }
+
+    fn record(&mut self, cave_id: NodeId, cave: &Cave) {
+        self.counts[cave_id] += 1;
+        if self.counts[cave_id] > 1 && !cave.is_large { self.small_cave_revisits += 1 }
+    }
+
+    fn unrecord(&mut self, cave_id: NodeId, cave: &Cave) {
+        if self.counts[cave_id] > 1 && !cave.is_large { self.small_cave_revisits -= 1 }
+        self.counts[cave_id] -= 1;
+    }
+
+    fn count(&self, cave_id: NodeId) -> u32 {
+        return self.counts[cave_id]
+    }
+
+    fn small_caves_bitset(&self, cave_system: &CaveSystem) -> Vec<u64> {
+        let mut words = vec![0u64; self.counts.len().div_ceil(64)];
+        for (id, cnt) in self.counts.iter().enumerate() {
+            if *cnt > 0 && !cave_system.cave(id).is_large { words[id / 64] |= 1u64 << (id % 64) }
+        }
+        return words
+    }
+}
+
+trait VisitRule {
+    // `visits` already include the visit to `cave_id`
+    fn allows(&self, cave_system: &CaveSystem, cave_id: NodeId, visits: &Visits) -> bool;
+
+    fn accepts(&self, _cave_system: &CaveSystem, _visits: &Visits) -> bool {
+        return true
+    }
+
+    // Paths reaching a cave with equal keys have the same continuations, so path
+    // counting can memoise on it. Rules can narrow it down to what they look at.
+    fn state_key(&self, _cave_system: &CaveSystem, visits: &Visits) -> Vec<u64> {
+        return visits.counts.iter().map(|cnt| *cnt as u64).collect()
     }
 }
 
@@ -50,85 +111,225 @@ enum PathRestrictions {
     V2,
 }
 
+impl VisitRule for PathRestrictions {
+    fn allows(&self, cave_system: &CaveSystem, cave_id: NodeId, visits: &Visits) -> bool {
+        if cave_system.cave(cave_id).is_large { return true }
+        return match self {
+            PathRestrictions::V1 => visits.count(cave_id) <= 1,
+            PathRestrictions::V2 => visits.small_cave_revisits <= 1
+        }
+    }
+
+    fn state_key(&self, cave_system: &CaveSystem, visits: &Visits) -> Vec<u64> {
+        let mut key = visits.small_caves_bitset(cave_system);
+        key.push(visits.small_cave_revisits as u64);
+        return key
+    }
+}
+
+// Declarative rules, e.g. "small=2,revisits=1,forbid=c|d,require=b":
+//   small=K      every small cave is visited at most K times
+//   revisits=N   at most N revisits of small caves in total
+//   large=M      every large cave is visited at most M times
+//   forbid=a|b   caves which are never entered
+//   require=a|b  caves which every path has to visit
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct RuleSet {
+    small_cave_visits: u32,
+    small_cave_revisits: Option<u32>,
+    large_cave_visits: Option<u32>,
+    forbidden: Vec<String>,
+    required: Vec<String>
+}
+
+impl RuleSet {
+    fn parse(encoded: &str) -> Result<RuleSet, String> {
+        let mut rules = RuleSet {
+            small_cave_visits: 1,
+            small_cave_revisits: None,
+            large_cave_visits: None,
+            forbidden: Vec::new(),
+            required: Vec::new()
+        };
+        for clause in encoded.split(",").map(|c| c.trim()).filter(|c| !c.is_empty()) {
+            let (name, value) = match clause.split_once("=") {
+                Some((name, value)) => (name.trim(), value.trim()),
+                None => return Err(format!("Rule {:?} is not of the form name=value", clause))
+            };
+            let number = || value.parse::<u32>()
+                .map_err(|_| format!("Rule {:?} expects a number, got {:?}", name, value));
+            let caves = || value.split("|").map(|c| c.trim().to_string()).collect::<Vec<String>>();
+            match name {
+                "small" => rules.small_cave_visits = number()?,
+                "revisits" => rules.small_cave_revisits = Some(number()?),
+                "large" => rules.large_cave_visits = Some(number()?),
+                "forbid" => rules.forbidden.extend(caves()),
+                "require" => rules.required.extend(caves()),
+                _ => return Err(format!("Unknown rule {:?}", name))
+            }
+        }
+        return Ok(rules)
+    }
+}
+
+impl VisitRule for RuleSet {
+    fn allows(&self, cave_system: &CaveSystem, cave_id: NodeId, visits: &Visits) -> bool {
+        let cave = cave_system.cave(cave_id);
+        if self.forbidden.contains(&cave.id) { return false }
+        if cave.is_large {
+            return self.large_cave_visits.map(|m| visits.count(cave_id) <= m).unwrap_or(true)
+        }
+        if visits.count(cave_id) > self.small_cave_visits { return false }
+        return self.small_cave_revisits.map(|n| visits.small_cave_revisits <= n).unwrap_or(true)
+    }
+
+    fn accepts(&self, cave_system: &CaveSystem, visits: &Visits) -> bool {
+        return self.required.iter().all(|id|
+            cave_system.graph.node_id(id).map(|c| visits.count(c) > 0).unwrap_or(false)
+        )
+    }
+}
+
+fn parse_visit_rule(encoded: &str) -> Result<Box<dyn VisitRule>, String> {
+    return match encoded {
+        "v1" | "V1" => Ok(Box::new(PathRestrictions::V1)),
+        "v2" | "V2" => Ok(Box::new(PathRestrictions::V2)),
+        _ => Ok(Box::new(RuleSet::parse(encoded)?))
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, Debug)]
 struct Path {
-    caves: Vec<Cave>,
-    starts_with: Cave,
-    ends_with: Cave,
-    caves_visits_count: HashMap<String, u32>,
-    small_caves_with_more_than_two_visits: u32
+    caves: Vec<NodeId>,
+    visits: Visits,
+    cost: u64
 }
 
 impl Path {
-    fn new(seed_cave: Cave) -> Path {
+    fn new(seed_cave: NodeId, n_caves: usize) -> Path {
         return Path {
-            caves: vec![seed_cave.clone()],
-            starts_with: seed_cave.clone(),
-            ends_with: seed_cave.clone(),
-            caves_visits_count: Default::default(),
-            small_caves_with_more_than_two_visits: 0
+            caves: vec![seed_cave],
+            visits: Visits::new(n_caves),
+            cost: 0
         }
     }
 
-    fn ids(&self) -> String {
+    fn ends_with(&self) -> NodeId {
+        return *self.caves.last().unwrap()
+    }
+
+    fn ids(&self, cave_system: &CaveSystem) -> String {
         return self.caves.iter()
-            .map(|p|p.repr())
+            .map(|id| cave_system.cave(*id).repr())
             .collect::<Vec<&str>>().join(",")
     }
 
-    fn append(&mut self, cave: Cave, path_restrictions: PathRestrictions) -> bool {
-        let cave_id = cave.id.clone();
-        let cnt = self.caves_visits_count
-            .entry(cave_id.clone()).or_insert(0);
-        *cnt += 1;
+    fn len(&self) -> usize {
+        return self.caves.len()
+    }
 
-        if *cnt > 1 && !cave.is_large { self.small_caves_with_more_than_two_visits += 1 }
+    fn passes_through(&self, cave_system: &CaveSystem, id: &str) -> bool {
+        return cave_system.graph.node_id(id).map(|c| self.caves.contains(&c)).unwrap_or(false)
+    }
 
-        match path_restrictions {
-            PathRestrictions::V1 => {
-                if *cnt > 1 && !cave.is_large {
-                    return false
-                }
-            },
-            PathRestrictions::V2 => {
-                if self.small_caves_with_more_than_two_visits > 1 && !cave.is_large {
-                    return false
-                }
-            }
-        }
+    // A rejected cave leaves the path untouched
+    fn append(&mut self, cave_id: NodeId, cave_system: &CaveSystem, rule: &dyn VisitRule) -> bool {
+        self.visits.record(cave_id, cave_system.cave(cave_id));
 
-        if *cnt > 1000 {
+        if !rule.allows(cave_system, cave_id, &self.visits) {
+            self.visits.unrecord(cave_id, cave_system.cave(cave_id));
             return false
         }
 
-        self.caves.push(cave.clone());
-        self.ends_with = cave;
+        self.cost += cave_system.graph.edge_weight(self.ends_with(), cave_id).unwrap();
+        self.caves.push(cave_id);
         return true
     }
+
+    fn pop(&mut self, cave_system: &CaveSystem) {
+        let cave_id = self.caves.pop().unwrap();
+        self.visits.unrecord(cave_id, cave_system.cave(cave_id));
+        self.cost -= cave_system.graph.edge_weight(self.ends_with(), cave_id).unwrap();
+    }
 }
 
-#[derive(Clone, Eq, PartialEq, Debug)]
-struct Paths {
-    paths: Vec<Path>
+// Depth-first walk over the valid paths from start to end. Only the path being
+// explored is kept, together with the next neighbour to try at every depth, so
+// memory grows with the length of a path rather than with the number of paths.
+// With a budget, walks are cut short as soon as they cost more than it.
+struct PathIter<'a> {
+    cave_system: &'a CaveSystem,
+    rule: &'a dyn VisitRule,
+    path: Path,
+    next_neighbour: Vec<usize>,
+    budget: Option<u64>
 }
 
-impl Paths {
-    fn seed() -> Paths {
-        let seed_cave = Cave::new(&"start".to_string());
-        let seed_path = Path::new(seed_cave);
-        return Paths { paths: vec![seed_path] }
+impl<'a> PathIter<'a> {
+    fn new(cave_system: &'a CaveSystem, rule: &'a dyn VisitRule) -> PathIter<'a> {
+        let n_caves = cave_system.graph.n_nodes();
+        return match (cave_system.graph.node_id("start"), cave_system.graph.node_id("end")) {
+            (Some(start), Some(_)) => PathIter {
+                cave_system, rule, path: Path::new(start, n_caves), next_neighbour: vec![0], budget: None
+            },
+            _ => PathIter {
+                cave_system, rule, path: Path::new(0, n_caves), next_neighbour: Vec::new(), budget: None
+            }
+        }
     }
 
-    fn new() -> Paths {
-        return Paths { paths: Default::default() }
+    fn with_budget(mut self, budget: u64) -> PathIter<'a> {
+        self.budget = Some(budget);
+        return self
     }
+}
 
-    fn add(&mut self, path: Path) {
-        self.paths.push(path);
+impl<'a> Iterator for PathIter<'a> {
+    type Item = Path;
+
+    fn next(&mut self) -> Option<Path> {
+        let cave_system = self.cave_system;
+        while let Some(idx) = self.next_neighbour.last_mut() {
+            let next = cave_system.graph.neighbours(self.path.ends_with()).nth(*idx).map(|(id, _)| id);
+            *idx += 1;
+            let next = match next {
+                Some(next) => next,
+                None => {
+                    self.next_neighbour.pop();
+                    if !self.next_neighbour.is_empty() { self.path.pop(cave_system) }
+                    continue
+                }
+            };
+            if !self.path.append(next, cave_system, self.rule) { continue }
+            if self.budget.map(|b| self.path.cost > b).unwrap_or(false) {
+                self.path.pop(cave_system);
+                continue
+            }
+            if !cave_system.cave(next).is_end {
+                self.next_neighbour.push(0);
+                continue
+            }
+            let found = if self.rule.accepts(cave_system, &self.path.visits) {
+                Some(self.path.clone())
+            } else {
+                None
+            };
+            self.path.pop(cave_system);
+            if found.is_some() { return found }
+        }
+        return None
     }
+}
 
+#[derive(Clone, Debug)]
+struct Paths<'a> {
+    cave_system: &'a CaveSystem,
+    paths: Vec<Path>
+}
+
+impl<'a> Paths<'a> {
     fn repr(&self) -> Vec<String> {
-        return self.paths.iter().map(|p| p.ids()).collect()
+        return self.paths.iter().map(|p| p.ids(self.cave_system)).collect()
     }
 
     fn len(&self) -> usize {
@@ -136,114 +337,547 @@ impl Paths {
     }
 }
 
+struct PathCounter<'a> {
+    cave_system: &'a CaveSystem,
+    end: NodeId,
+    rule: &'a dyn VisitRule,
+    memo: HashMap<(NodeId, Vec<u64>), u64>
+}
+
+impl<'a> PathCounter<'a> {
+    fn count_from(&mut self, cave_id: NodeId, visits: &Visits) -> u64 {
+        if cave_id == self.end {
+            return if self.rule.accepts(self.cave_system, visits) { 1 } else { 0 }
+        }
+        let key = (cave_id, self.rule.state_key(self.cave_system, visits));
+        if let Some(cnt) = self.memo.get(&key) { return *cnt }
+
+        let cave_system = self.cave_system;
+        let mut cnt = 0;
+        for (next, _) in cave_system.graph.neighbours(cave_id) {
+            let mut next_visits = visits.clone();
+            next_visits.record(next, cave_system.cave(next));
+            if !self.rule.allows(cave_system, next, &next_visits) { continue }
+            cnt += self.count_from(next, &next_visits);
+        }
+
+        self.memo.insert(key, cnt);
+        return cnt
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct CaveUsage {
+    cave: String,
+    visits: u64,
+    paths_through: u64
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct ConnectionUsage {
+    start: String,
+    end: String,
+    paths: u64
+}
+
+// Structural checks ignore visiting rules, usage figures count the valid paths
+// under the chosen rule. All lists are sorted by cave name.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct CaveReport {
+    n_paths: u64,
+    unreachable_from_start: Vec<String>,
+    cannot_reach_end: Vec<String>,
+    dead_ends: Vec<String>,
+    on_every_path: Vec<String>,
+    caves: Vec<CaveUsage>,
+    connections: Vec<ConnectionUsage>
+}
+
+impl CaveReport {
+    fn repr(&self) -> Vec<String> {
+        let list = |caves: &Vec<String>| if caves.is_empty() { "-".to_string() } else { caves.join(", ") };
+        let mut lines = vec![
+            format!("Valid paths: {}", self.n_paths),
+            format!("Unreachable from start: {}", list(&self.unreachable_from_start)),
+            format!("Cannot reach end: {}", list(&self.cannot_reach_end)),
+            format!("Dead-end small caves: {}", list(&self.dead_ends)),
+            format!("Caves on every path: {}", list(&self.on_every_path)),
+            "Cave visits (visits / paths through):".to_string()
+        ];
+        lines.extend(self.caves.iter().map(|c| format!("  {} {} / {}", c.cave, c.visits, c.paths_through)));
+        lines.push("Connection usage:".to_string());
+        lines.extend(self.connections.iter().map(|c| format!("  {} -> {} {}", c.start, c.end, c.paths)));
+        return lines
+    }
+
+    fn to_json(&self) -> String {
+        let list = |caves: &Vec<String>| json_array(caves.iter().map(|c| json_string(c)));
+        let caves = json_array(self.caves.iter().map(|c| format!(
+            "{{\"cave\": {}, \"visits\": {}, \"paths_through\": {}}}", json_string(&c.cave), c.visits, c.paths_through
+        )));
+        let connections = json_array(self.connections.iter().map(|c| format!(
+            "{{\"start\": {}, \"end\": {}, \"paths\": {}}}", json_string(&c.start), json_string(&c.end), c.paths
+        )));
+        return format!(
+            "{{\"n_paths\": {}, \"unreachable_from_start\": {}, \"cannot_reach_end\": {}, \"dead_ends\": {}, \
+             \"on_every_path\": {}, \"caves\": {}, \"connections\": {}}}",
+            self.n_paths, list(&self.unreachable_from_start), list(&self.cannot_reach_end), list(&self.dead_ends),
+            list(&self.on_every_path), caves, connections
+        )
+    }
+}
+
+// What to draw on top of the plain cave graph in `CaveSystem::to_dot`
+enum DotOverlay<'a> {
+    Plain,
+    Path(&'a Path),
+    EdgeUsage(&'a dyn VisitRule)
+}
+
 #[derive(Debug)]
 struct CaveSystem {
-    connections: HashMap<String, HashSet<Connection>>
+    graph: Graph<Cave, u64>
 }
 
 impl CaveSystem {
-    const MAX_ITER: u32 = 30;
-
     fn new() -> CaveSystem {
-        return CaveSystem { connections: Default::default() }
+        return CaveSystem { graph: Graph::directed() }
+    }
+
+    fn cave(&self, id: NodeId) -> &Cave {
+        return self.graph.attribute(id)
     }
 
     fn add_connection(&mut self, conn: Connection) {
-        let current = self.connections.entry(conn.start.id.clone())
-            .or_insert(HashSet::new());
-        current.insert(conn);
+        let start = self.graph.intern(&conn.start, || Cave::new(&conn.start));
+        let end = self.graph.intern(&conn.end, || Cave::new(&conn.end));
+        self.graph.add_edge(start, end, conn.weight);
     }
 
-    fn from_strings(pairs: &Vec<String>) -> CaveSystem {
+    fn from_strings(pairs: &Vec<String>) -> Result<CaveSystem, String> {
+        let mut parsed_pairs = Vec::with_capacity(pairs.len());
+        for (line_id, encoded_pair) in pairs.iter().enumerate() {
+            if encoded_pair.trim().is_empty() { continue }
+            parsed_pairs.push(CaveSystem::_parse_pair(line_id + 1, encoded_pair.trim())?)
+        }
+
         let mut cave_system = CaveSystem::new();
-        let processed_pairs: Vec<Connection> = pairs.iter()
-            .map(|encoded_pair| encoded_pair.split("-").collect::<Vec<&str>>())
-            .map(|v|(v.clone().first().unwrap().clone(), v.last().unwrap().clone()))
-            .map(|(from, to)|(Cave::new(&from.to_string()), Cave::new(&to.to_string())))
-            .map(|(from, to)|
-                if to.is_start { (to, from) }
-                else { (from, to) })
-            .map(|(from, to)|
-                if from.is_end { (to, from) }
-                else { (from, to) })
-            .map(|(from, to)| {
+        let processed_pairs: Vec<Connection> = parsed_pairs.into_iter()
+            .map(|(from, to, weight)|(Cave::new(&from), Cave::new(&to), weight))
+            .map(|(from, to, weight)|
+                if to.is_start { (to, from, weight) }
+                else { (from, to, weight) })
+            .map(|(from, to, weight)|
+                if from.is_end { (to, from, weight) }
+                else { (from, to, weight) })
+            .flat_map(|(from, to, weight)| {
                 if !from.is_start && !to.is_end { vec![
-                    Connection { start: from.clone(), end: to.clone() },
-                    Connection { start: to, end: from }
+                    Connection::new(&from.id, &to.id).with_weight(weight),
+                    Connection::new(&to.id, &from.id).with_weight(weight)
                 ]}
                 else {
                     vec![
-                        Connection { start: from, end: to },
+                        Connection::new(&from.id, &to.id).with_weight(weight),
                     ]
                 }
             })
-            .flatten()
             .collect();
 
         for p in processed_pairs {
             cave_system.add_connection(p);
         }
-        
-        return cave_system
-    }
-
-    fn compute_all_paths(&self, path_restrictions: PathRestrictions) -> Paths {
-        let mut paths = Paths::seed();
-        let mut iter_id = 0;
-        let mut some_path_does_not_have_end = true;
-        while iter_id < CaveSystem::MAX_ITER && some_path_does_not_have_end {
-            let mut new_paths = Paths::new();
-            some_path_does_not_have_end = false;
-
-            for path in &paths.paths {
-                let c = self.connections.get(&path.ends_with.id);
-                match c {
-                    Some(conns) => {
-                        for conn in conns {
-                            let mut new_path = path.clone();
-                            let is_accepted = new_path.append(
-                                conn.end.clone(),
-                                path_restrictions
-                            );
-                            if is_accepted {
-                                new_paths.add(new_path);
-                            }
-                        }
-                    }
-                    _ => {
-                        new_paths.add(path.clone());
-                    }
-                }
 
-                if !path.ends_with.is_end {
-                    some_path_does_not_have_end = true
-                }
+        cave_system.validate()?;
+        return Ok(cave_system)
+    }
+
+    // "A-b" or, with a weight other than the default 1, "A-b:5"
+    fn _parse_pair(line_number: usize, encoded_pair: &str) -> Result<(String, String, u64), String> {
+        let (encoded_pair, weight) = match encoded_pair.split_once(":") {
+            Some((pair, weight)) => (pair.trim(), weight.trim().parse::<u64>().map_err(|_|
+                format!("Line {}: weight {:?} is not a non-negative integer", line_number, weight.trim())
+            )?),
+            None => (encoded_pair, 1)
+        };
+        let ids: Vec<&str> = encoded_pair.split("-").map(|id| id.trim()).collect();
+        if ids.len() != 2 {
+            return Err(format!("Line {}: {:?} is not of the form cave-cave", line_number, encoded_pair))
+        }
+        for id in &ids {
+            let is_word = !id.is_empty() && id.chars().all(|c| c.is_ascii_alphabetic());
+            if !is_word || (id.to_uppercase() != *id && id.to_lowercase() != *id) {
+                return Err(format!(
+                    "Line {}: cave name {:?} has to be made of only upper case or only lower case letters",
+                    line_number, id
+                ))
+            }
+        }
+        return Ok((ids[0].to_string(), ids[1].to_string(), weight))
+    }
+
+    // Paths could bounce between two connected large caves forever, so such inputs
+    // are rejected instead of relying on an iteration cap
+    fn validate(&self) -> Result<(), String> {
+        for id in vec!["start", "end"] {
+            if self.graph.node_id(id).is_none() { return Err(format!("There is no {} cave", id)) }
+        }
+        let mut large_pairs: Vec<String> = self.graph.edges()
+            .filter(|(from, to, _)| self.cave(*from).is_large && self.cave(*to).is_large)
+            .map(|(from, to, _)| (self.graph.name(from), self.graph.name(to)))
+            .filter(|(from, to)| from <= to)
+            .map(|(from, to)| format!("{}-{}", from, to))
+            .collect();
+        if !large_pairs.is_empty() {
+            large_pairs.sort();
+            return Err(format!(
+                "Large caves are connected to each other ({}), so there are infinitely many paths",
+                large_pairs.join(", ")
+            ))
+        }
+        return Ok(())
+    }
+
+    fn count_all_paths(&self, rule: &dyn VisitRule) -> u64 {
+        let (start, end) = match (self.graph.node_id("start"), self.graph.node_id("end")) {
+            (Some(start), Some(end)) => (start, end),
+            _ => return 0
+        };
+        let mut counter = PathCounter { cave_system: self, end, rule, memo: HashMap::new() };
+        return counter.count_from(start, &Visits::new(self.graph.n_nodes()))
+    }
+
+    fn paths<'a>(&'a self, rule: &'a dyn VisitRule) -> PathIter<'a> {
+        return PathIter::new(self, rule)
+    }
+
+    // One path per line, e.g. start,A,b,end
+    fn write_paths<I>(&self, paths: I, file_path: &str) -> Result<usize, String> where I: Iterator<Item=Path> {
+        let file = File::create(file_path).map_err(|e| format!("Cannot create {}: {}", file_path, e))?;
+        let mut out = BufWriter::new(file);
+        let mut cnt = 0;
+        for path in paths {
+            writeln!(out, "{}", path.ids(self)).map_err(|e| format!("Cannot write {}: {}", file_path, e))?;
+            cnt += 1;
+        }
+        out.flush().map_err(|e| format!("Cannot write {}: {}", file_path, e))?;
+        return Ok(cnt)
+    }
+
+    // How many valid paths take each connection, connections taken by none are left out
+    fn edge_usage(&self, rule: &dyn VisitRule) -> HashMap<(NodeId, NodeId), u64> {
+        let mut usage = HashMap::new();
+        for path in self.paths(rule) {
+            for step in path.caves.windows(2) {
+                *usage.entry((step[0], step[1])).or_insert(0) += 1;
             }
+        }
+        return usage
+    }
+
+    // Caves and connections are listed by name, so equal systems give equal output
+    fn to_dot(&self, overlay: DotOverlay) -> String {
+        let mut steps: HashMap<(NodeId, NodeId), Vec<usize>> = HashMap::new();
+        let mut usage = HashMap::new();
+        match overlay {
+            DotOverlay::Plain => {},
+            DotOverlay::Path(path) => for (idx, step) in path.caves.windows(2).enumerate() {
+                steps.entry((step[0], step[1])).or_default().push(idx + 1)
+            },
+            DotOverlay::EdgeUsage(rule) => usage = self.edge_usage(rule)
+        }
+        let max_usage = usage.values().cloned().max().unwrap_or(0);
+        let on_path = |id: NodeId| steps.keys().any(|(from, to)| *from == id || *to == id);
 
-            paths = new_paths;
-            iter_id += 1;
+        let mut caves: Vec<NodeId> = self.graph.node_ids().collect();
+        caves.sort_by_key(|id| self.graph.name(*id));
+        let mut dot = String::from("digraph caves {\n");
+        for id in caves.iter().cloned() {
+            let cave = self.cave(id);
+            let mut attributes = vec![if cave.is_start {
+                "shape=doublecircle, style=filled, fillcolor=palegreen"
+            } else if cave.is_end {
+                "shape=doublecircle, style=filled, fillcolor=lightpink"
+            } else if cave.is_large {
+                "shape=box, style=filled, fillcolor=lightblue"
+            } else {
+                "shape=circle"
+            }.to_string()];
+            if on_path(id) { attributes.push("color=red, penwidth=2".to_string()) }
+            dot.push_str(&format!("    {} [{}];\n", dot_id(cave.repr()), attributes.join(", ")));
+        }
+
+        let mut connections: Vec<(NodeId, NodeId)> = self.graph.edges().map(|(from, to, _)| (from, to)).collect();
+        connections.sort_by_key(|(from, to)| (self.graph.name(*from), self.graph.name(*to)));
+        for (from, to) in connections {
+            let mut attributes = Vec::new();
+            if let Some(idx) = steps.get(&(from, to)) {
+                let idx: Vec<String> = idx.iter().map(|i| i.to_string()).collect();
+                attributes.push(format!("color=red, penwidth=2, label=\"{}\"", idx.join(",")));
+            }
+            if let DotOverlay::EdgeUsage(_) = overlay {
+                let cnt = usage.get(&(from, to)).cloned().unwrap_or(0);
+                let width = if max_usage > 0 { 1.0 + 4.0 * cnt as f64 / max_usage as f64 } else { 1.0 };
+                attributes.push(format!("label=\"{}\", penwidth={:.1}", cnt, width));
+                if cnt == 0 { attributes.push("style=dashed".to_string()) }
+            }
+            let attributes = if attributes.is_empty() { String::new() }
+                else { format!(" [{}]", attributes.join(", ")) };
+            dot.push_str(&format!(
+                "    {} -> {}{};\n", dot_id(self.graph.name(from)), dot_id(self.graph.name(to)), attributes
+            ));
+        }
+        dot.push_str("}\n");
+        return dot
+    }
 
-            if !some_path_does_not_have_end {
-                println!("Early stopped at iter={}", iter_id)
+    // Caves reachable from `from` following connections forwards, or backwards when
+    // `forward` is false
+    fn _reachable(&self, from: NodeId, forward: bool) -> Vec<bool> {
+        let mut incoming: Vec<Vec<NodeId>> = vec![Vec::new(); self.graph.n_nodes()];
+        if !forward {
+            for (start, end, _) in self.graph.edges() { incoming[end].push(start) }
+        }
+        let mut seen = vec![false; self.graph.n_nodes()];
+        let mut stack = vec![from];
+        seen[from] = true;
+        while let Some(id) = stack.pop() {
+            let next: Vec<NodeId> = if forward { self.graph.neighbours(id).map(|(n, _)| n).collect() }
+                else { incoming[id].clone() };
+            for n in next {
+                if seen[n] { continue }
+                seen[n] = true;
+                stack.push(n)
             }
         }
-        return paths
+        return seen
+    }
+
+    fn _sorted_names<I>(&self, ids: I) -> Vec<String> where I: Iterator<Item=NodeId> {
+        let mut names: Vec<String> = ids.map(|id| self.graph.name(id).to_string()).collect();
+        names.sort();
+        return names
     }
+
+    fn analyse(&self, rule: &dyn VisitRule) -> CaveReport {
+        let n_caves = self.graph.n_nodes();
+        let start = self.graph.node_id("start");
+        let end = self.graph.node_id("end");
+        let from_start = start.map(|s| self._reachable(s, true)).unwrap_or_else(|| vec![false; n_caves]);
+        let to_end = end.map(|e| self._reachable(e, false)).unwrap_or_else(|| vec![false; n_caves]);
+
+        // Entering a small cave whose only neighbour is another small cave leaves no way
+        // on, as going straight back would visit that one twice. Hanging off a large cave
+        // it is a detour like A,c,A instead.
+        let dead_ends = self._sorted_names(self.graph.node_ids().filter(|id| {
+            let cave = self.cave(*id);
+            !cave.is_large && !cave.is_start && !cave.is_end && self.graph.degree(*id) <= 1
+                && self.graph.neighbours(*id).all(|(n, _)| !self.cave(n).is_end && !self.cave(n).is_large)
+        }));
+
+        let mut visits = vec![0u64; n_caves];
+        let mut paths_through = vec![0u64; n_caves];
+        let mut usage: HashMap<(NodeId, NodeId), u64> = HashMap::new();
+        let mut n_paths = 0;
+        for path in self.paths(rule) {
+            n_paths += 1;
+            for id in &path.caves { visits[*id] += 1 }
+            for id in self.graph.node_ids().filter(|id| path.caves.contains(id)) { paths_through[id] += 1 }
+            for step in path.caves.windows(2) { *usage.entry((step[0], step[1])).or_insert(0) += 1 }
+        }
+
+        // Counted on the paths the rule allows, as it may rule out detours around a cave
+        let on_every_path = if n_paths == 0 { Vec::new() } else {
+            self._sorted_names(self.graph.node_ids()
+                .filter(|id| Some(*id) != start && Some(*id) != end && paths_through[*id] == n_paths))
+        };
+
+        let mut caves: Vec<CaveUsage> = self.graph.node_ids().map(|id| CaveUsage {
+            cave: self.graph.name(id).to_string(),
+            visits: visits[id],
+            paths_through: paths_through[id]
+        }).collect();
+        caves.sort_by(|a, b| a.cave.cmp(&b.cave));
+        let mut connections: Vec<ConnectionUsage> = self.graph.edges().map(|(from, to, _)| ConnectionUsage {
+            start: self.graph.name(from).to_string(),
+            end: self.graph.name(to).to_string(),
+            paths: usage.get(&(from, to)).cloned().unwrap_or(0)
+        }).collect();
+        connections.sort_by(|a, b| (&a.start, &a.end).cmp(&(&b.start, &b.end)));
+
+        return CaveReport {
+            n_paths,
+            unreachable_from_start: self._sorted_names(self.graph.node_ids().filter(|id| !from_start[*id])),
+            cannot_reach_end: self._sorted_names(self.graph.node_ids().filter(|id| !to_end[*id])),
+            dead_ends,
+            on_every_path,
+            caves,
+            connections
+        }
+    }
+
+    // Valid paths costing at most `budget`, without exploring walks beyond it
+    fn paths_within<'a>(&'a self, rule: &'a dyn VisitRule, budget: u64) -> PathIter<'a> {
+        return PathIter::new(self, rule).with_budget(budget)
+    }
+
+    // Ties are broken by the caves visited, in the start,A,b,end notation
+    fn cheapest_path(&self, rule: &dyn VisitRule) -> Option<Path> {
+        return self.paths(rule).min_by_key(|p| (p.cost, p.ids(self)))
+    }
+
+    fn most_expensive_path(&self, rule: &dyn VisitRule) -> Option<Path> {
+        return self.paths(rule).min_by_key(|p| (Reverse(p.cost), p.ids(self)))
+    }
+
+    fn compute_all_paths(&self, rule: &dyn VisitRule) -> Paths<'_> {
+        return Paths { cave_system: self, paths: self.paths(rule).collect() }
+    }
+}
+
+fn load_cave_system(filename: &str) -> CaveSystem {
+    return CaveSystem::from_strings(&read_lines_as_str_vector(filename))
+        .unwrap_or_else(|e| panic!("Invalid cave system in {}: {}", filename, e))
 }
 
 pub fn day_12() {
     let filename= "data/day-12-passage-pathing/data-part-1.txt";
-    let lines = read_lines_as_str_vector(filename);
-    let cave_system = CaveSystem::from_strings(&lines);
-    let result = cave_system.compute_all_paths(PathRestrictions::V1);
-    println!("Day 12 Part 1 result: {res}", res=result.len());
+    let cave_system = load_cave_system(filename);
+    let result = cave_system.count_all_paths(&PathRestrictions::V1);
+    println!("Day 12 Part 1 result: {res}", res=result);
+
+    let result = cave_system.count_all_paths(&PathRestrictions::V2);
+    println!("Day 12 Part 2 result: {res}", res=result);
+}
+
+// Removes `name` and the value following it from the arguments
+fn take_option(args: &mut Vec<String>, name: &str, usage: &str) -> Option<String> {
+    let idx = args.iter().position(|a| a == name)?;
+    if idx + 1 >= args.len() {
+        panic!("{}", usage)
+    }
+    let value = args.remove(idx + 1);
+    args.remove(idx);
+    return Some(value)
+}
+
+// Usage: caves <input-file> [rule] [output-file|-] [--budget N] [--through CAVE] [--max-caves N],
+// where rule is v1, v2 or a declarative rule set. With an output file every path is
+// written to it, with - the paths are collected and printed after their count. The
+// options keep only the paths costing at most N, passing through CAVE or visiting at
+// most N caves, counting start and end.
+pub fn caves_command(args: &[String]) {
+    let usage = "Usage: caves <input-file> [v1|v2|rules] [output-file|-] [--budget N] [--through CAVE] \
+        [--max-caves N]";
+    let mut args = args.to_vec();
+    let budget = take_option(&mut args, "--budget", usage)
+        .map(|b| b.parse::<u64>().unwrap_or_else(|_| panic!("{}", usage)));
+    let through = take_option(&mut args, "--through", usage);
+    let max_caves = take_option(&mut args, "--max-caves", usage)
+        .map(|n| n.parse::<usize>().unwrap_or_else(|_| panic!("{}", usage)));
+    let filename = match args.first() {
+        Some(filename) => filename,
+        None => panic!("{}", usage)
+    };
+    let encoded_rule = args.get(1).map(|r| r.as_str()).unwrap_or("v1");
+    let rule = parse_visit_rule(encoded_rule)
+        .unwrap_or_else(|e| panic!("Invalid rule {:?}: {}", encoded_rule, e));
+    let cave_system = load_cave_system(filename);
+    if let Some(cave) = &through {
+        if cave_system.graph.node_id(cave).is_none() {
+            panic!("Unknown cave {:?} in {}", cave, filename)
+        }
+    }
+
+    let mut under = encoded_rule.to_string();
+    if let Some(budget) = budget {
+        under += &format!(" within {}", budget)
+    }
+    if let Some(cave) = &through {
+        under += &format!(" through {}", cave)
+    }
+    if let Some(max_caves) = max_caves {
+        under += &format!(" visiting at most {} caves", max_caves)
+    }
+    let filtered = budget.is_some() || through.is_some() || max_caves.is_some();
+    let paths = match budget {
+        Some(budget) => cave_system.paths_within(rule.as_ref(), budget),
+        None => cave_system.paths(rule.as_ref())
+    };
+    let paths = paths
+        .filter(|p| through.as_ref().map(|cave| p.passes_through(&cave_system, cave)).unwrap_or(true))
+        .filter(|p| max_caves.map(|n| p.len() <= n).unwrap_or(true));
+    match args.get(2) {
+        Some(output) if output == "-" => {
+            let paths = if filtered {
+                Paths { cave_system: &cave_system, paths: paths.collect() }
+            } else {
+                cave_system.compute_all_paths(rule.as_ref())
+            };
+            println!("Paths under {}: {}", under, paths.len());
+            paths.repr().iter().for_each(|path| println!("{}", path))
+        },
+        Some(output) => {
+            let cnt = cave_system.write_paths(paths, output)
+                .unwrap_or_else(|e| panic!("{}", e));
+            println!("Wrote {} paths under {} to {}", cnt, under, output)
+        },
+        None if filtered => println!("Paths under {}: {}", under, paths.count()),
+        None => println!("Paths under {}: {}", under, cave_system.count_all_paths(rule.as_ref()))
+    }
+}
 
-    let result = cave_system.compute_all_paths(PathRestrictions::V2);
-    println!("Day 12 Part 2 result: {res}", res=result.len());
+// Usage: caves-dot <input-file> [rule] [usage|cheapest|most-expensive], prints the
+// cave graph in Graphviz DOT. With a rule every connection is labelled with the
+// number of paths taking it, or the cheapest or most expensive path is highlighted.
+pub fn caves_dot_command(args: &[String]) {
+    let usage = "Usage: caves-dot <input-file> [v1|v2|rules] [usage|cheapest|most-expensive]";
+    let filename = match args.first() {
+        Some(filename) => filename,
+        None => panic!("{}", usage)
+    };
+    let cave_system = load_cave_system(filename);
+    let dot = match args.get(1) {
+        Some(encoded_rule) => {
+            let rule = parse_visit_rule(encoded_rule)
+                .unwrap_or_else(|e| panic!("Invalid rule {:?}: {}", encoded_rule, e));
+            let highlighted = |path: Option<Path>| match path {
+                Some(path) => cave_system.to_dot(DotOverlay::Path(&path)),
+                None => panic!("No path from start to end under {}", encoded_rule)
+            };
+            match args.get(2).map(|o| o.as_str()).unwrap_or("usage") {
+                "usage" => cave_system.to_dot(DotOverlay::EdgeUsage(rule.as_ref())),
+                "cheapest" => highlighted(cave_system.cheapest_path(rule.as_ref())),
+                "most-expensive" => highlighted(cave_system.most_expensive_path(rule.as_ref())),
+                _ => panic!("{}", usage)
+            }
+        },
+        None => cave_system.to_dot(DotOverlay::Plain)
+    };
+    print!("{}", dot)
+}
+
+// Usage: caves-report <input-file> [rule] [text|json], prints reachability and usage figures
+pub fn caves_report_command(args: &[String]) {
+    let filename = match args.first() {
+        Some(filename) => filename,
+        None => panic!("Usage: caves-report <input-file> [v1|v2|rules] [text|json]")
+    };
+    let encoded_rule = args.get(1).map(|r| r.as_str()).unwrap_or("v1");
+    let rule = parse_visit_rule(encoded_rule)
+        .unwrap_or_else(|e| panic!("Invalid rule {:?}: {}", encoded_rule, e));
+    let cave_system = load_cave_system(filename);
+    let report = cave_system.analyse(rule.as_ref());
+    match args.get(2).map(|f| f.as_str()).unwrap_or("text") {
+        "text" => report.repr().iter().for_each(|line| println!("{}", line)),
+        "json" => println!("{}", report.to_json()),
+        format => panic!("Unknown output format {:?}, expected text or json", format)
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::env;
+    use std::fs;
     use crate::exs::utils::strs_to_strings;
     use super::*;
 
@@ -258,7 +892,7 @@ mod tests {
         cave_system.add_connection(
             Connection::new(&"a".to_string(), &"end".to_string())
         );
-        assert_eq!(cave_system.compute_all_paths(path_restrictions).repr(), vec!["start,a,end"]);
+        assert_eq!(cave_system.compute_all_paths(&path_restrictions).repr(), vec!["start,a,end"]);
 
         cave_system.add_connection(
             Connection::new(&"start".to_string(), &"A".to_string())
@@ -273,7 +907,7 @@ mod tests {
             Connection::new(&"A".to_string(), &"end".to_string())
         );
         assert_eq!(
-            cave_system.compute_all_paths(path_restrictions).repr().sort(),
+            cave_system.compute_all_paths(&path_restrictions).repr().sort(),
             vec![
                 "start,a,end",
                 "start,A,end",
@@ -296,8 +930,8 @@ mod tests {
             "A-end",
             "b-end"
         ]);
-        let cave_system = CaveSystem::from_strings(&inputs);
-        assert_eq!(cave_system.compute_all_paths(path_restrictions).len(), 10)
+        let cave_system = CaveSystem::from_strings(&inputs).unwrap();
+        assert_eq!(cave_system.compute_all_paths(&path_restrictions).len(), 10)
     }
 
     #[test]
@@ -315,9 +949,9 @@ mod tests {
             "kj-HN",
             "kj-dc"
         ]);
-        let cave_system = CaveSystem::from_strings(&inputs);
+        let cave_system = CaveSystem::from_strings(&inputs).unwrap();
         assert_eq!(
-            cave_system.compute_all_paths(path_restrictions).repr().sort(),
+            cave_system.compute_all_paths(&path_restrictions).repr().sort(),
             vec![
                 "start,HN,dc,HN,end",
                 "start,HN,dc,HN,kj,HN,end",
@@ -365,8 +999,8 @@ mod tests {
             "pj-fs",
             "start-RW"
         ]);
-        let cave_system = CaveSystem::from_strings(&inputs);
-        assert_eq!(cave_system.compute_all_paths(path_restrictions).len(), 226)
+        let cave_system = CaveSystem::from_strings(&inputs).unwrap();
+        assert_eq!(cave_system.compute_all_paths(&path_restrictions).len(), 226)
     }
 
     #[test]
@@ -381,8 +1015,8 @@ mod tests {
             "A-end",
             "b-end"
         ]);
-        let cave_system = CaveSystem::from_strings(&inputs);
-        assert_eq!(cave_system.compute_all_paths(path_restrictions).len(), 36)
+        let cave_system = CaveSystem::from_strings(&inputs).unwrap();
+        assert_eq!(cave_system.compute_all_paths(&path_restrictions).len(), 36)
     }
 
     #[test]
@@ -400,8 +1034,8 @@ mod tests {
             "kj-HN",
             "kj-dc"
         ]);
-        let cave_system = CaveSystem::from_strings(&inputs);
-        assert_eq!(cave_system.compute_all_paths(path_restrictions).len(), 103);
+        let cave_system = CaveSystem::from_strings(&inputs).unwrap();
+        assert_eq!(cave_system.compute_all_paths(&path_restrictions).len(), 103);
     }
 
     #[test]
@@ -427,7 +1061,340 @@ mod tests {
             "pj-fs",
             "start-RW"
         ]);
-        let cave_system = CaveSystem::from_strings(&inputs);
-        assert_eq!(cave_system.compute_all_paths(path_restrictions).len(), 3509)
+        let cave_system = CaveSystem::from_strings(&inputs).unwrap();
+        assert_eq!(cave_system.compute_all_paths(&path_restrictions).len(), 3509)
+    }
+
+    #[test]
+    fn count_paths_agrees_with_enumeration() {
+        let examples = vec![
+            (vec!["start-A", "start-b", "A-c", "A-b", "b-d", "A-end", "b-end"], 10, 36),
+            (
+                vec![
+                    "dc-end", "HN-start", "start-kj", "dc-start", "dc-HN",
+                    "LN-dc", "HN-end", "kj-sa", "kj-HN", "kj-dc"
+                ],
+                19,
+                103
+            ),
+            (
+                vec![
+                    "fs-end", "he-DX", "fs-he", "start-DX", "pj-DX", "end-zg",
+                    "zg-sl", "zg-pj", "pj-he", "RW-he", "fs-DX", "pj-RW",
+                    "zg-RW", "start-pj", "he-WI", "zg-he", "pj-fs", "start-RW"
+                ],
+                226,
+                3509
+            )
+        ];
+        for (inputs, expected_v1, expected_v2) in examples {
+            let cave_system = CaveSystem::from_strings(&strs_to_strings(&inputs)).unwrap();
+            for (path_restrictions, expected) in vec![
+                (PathRestrictions::V1, expected_v1),
+                (PathRestrictions::V2, expected_v2)
+            ] {
+                let cnt = cave_system.count_all_paths(&path_restrictions);
+                assert_eq!(cnt, expected);
+                assert_eq!(cnt, cave_system.compute_all_paths(&path_restrictions).len() as u64);
+            }
+        }
+    }
+
+    #[test]
+    fn count_paths_without_start_or_end() {
+        let mut cave_system = CaveSystem::new();
+        cave_system.add_connection(Connection::new(&"start".to_string(), &"a".to_string()));
+        assert_eq!(cave_system.count_all_paths(&PathRestrictions::V1), 0);
+        assert_eq!(cave_system.paths(&PathRestrictions::V1).count(), 0);
+        assert_eq!(cave_system.compute_all_paths(&PathRestrictions::V1).len(), 0);
+    }
+
+    #[test]
+    fn reject_ill_posed_inputs() {
+        let error = |lines: Vec<&str>| CaveSystem::from_strings(&strs_to_strings(&lines)).unwrap_err();
+        assert_eq!(
+            error(vec!["start-A", "A-b", "A-end", "b-end", "b-CD", "CD-A"]),
+            "Large caves are connected to each other (A-CD), so there are infinitely many paths"
+        );
+        assert_eq!(error(vec!["start-a", "a-b"]), "There is no end cave");
+        assert_eq!(error(vec!["a-end"]), "There is no start cave");
+        assert_eq!(error(vec!["start-a", "a-end", "a"]), "Line 3: \"a\" is not of the form cave-cave");
+        assert_eq!(error(vec!["start-a", "a-b-end"]), "Line 2: \"a-b-end\" is not of the form cave-cave");
+        assert_eq!(
+            error(vec!["start-Ab", "Ab-end"]),
+            "Line 1: cave name \"Ab\" has to be made of only upper case or only lower case letters"
+        );
+        assert_eq!(
+            error(vec!["start-a", "a-"]),
+            "Line 2: cave name \"\" has to be made of only upper case or only lower case letters"
+        );
+        assert!(CaveSystem::from_strings(&strs_to_strings(&vec!["start-a", "", "a-end"])).is_ok());
+    }
+
+    #[test]
+    fn parse_rule_set() {
+        assert_eq!(
+            RuleSet::parse("small=2, revisits=1,forbid=c|d,require=b,large=3").unwrap(),
+            RuleSet {
+                small_cave_visits: 2,
+                small_cave_revisits: Some(1),
+                large_cave_visits: Some(3),
+                forbidden: strs_to_strings(&vec!["c", "d"]),
+                required: strs_to_strings(&vec!["b"])
+            }
+        );
+        assert!(RuleSet::parse("small").is_err());
+        assert!(RuleSet::parse("small=two").is_err());
+        assert!(RuleSet::parse("tiny=1").is_err());
+        assert!(parse_visit_rule("v2").is_ok());
+    }
+
+    #[test]
+    fn rule_sets_reproduce_built_in_restrictions() {
+        let inputs = strs_to_strings(&vec![
+            "dc-end", "HN-start", "start-kj", "dc-start", "dc-HN",
+            "LN-dc", "HN-end", "kj-sa", "kj-HN", "kj-dc"
+        ]);
+        let cave_system = CaveSystem::from_strings(&inputs).unwrap();
+        let v1 = RuleSet::parse("small=1").unwrap();
+        let v2 = RuleSet::parse("small=2,revisits=1").unwrap();
+        assert_eq!(cave_system.count_all_paths(&v1), 19);
+        assert_eq!(cave_system.compute_all_paths(&v1).len(), 19);
+        assert_eq!(cave_system.count_all_paths(&v2), 103);
+        assert_eq!(cave_system.compute_all_paths(&v2).len(), 103);
+    }
+
+    #[test]
+    fn custom_rule_sets() {
+        let inputs = strs_to_strings(&vec![
+            "start-A", "start-b", "A-c", "A-b", "b-d", "A-end", "b-end"
+        ]);
+        let cave_system = CaveSystem::from_strings(&inputs).unwrap();
+        let count = |encoded: &str| {
+            let rule = parse_visit_rule(encoded).unwrap();
+            let cnt = cave_system.count_all_paths(rule.as_ref());
+            assert_eq!(cnt, cave_system.compute_all_paths(rule.as_ref()).len() as u64);
+            cnt
+        };
+
+        assert_eq!(count("v1"), 10);
+        assert_eq!(count("forbid=c"), 5);
+        assert_eq!(count("require=c"), 5);
+        assert_eq!(count("large=1"), 4);
+        assert_eq!(count("small=2,revisits=2"), 54);
+        let mut paths = cave_system.compute_all_paths(&RuleSet::parse("require=c,large=2").unwrap())
+            .repr();
+        paths.sort();
+        assert_eq!(paths, vec!["start,A,c,A,b,end", "start,A,c,A,end", "start,b,A,c,A,end"]);
+    }
+
+    #[test]
+    fn streamed_paths_agree_with_enumeration() {
+        let inputs = strs_to_strings(&vec![
+            "dc-end", "HN-start", "start-kj", "dc-start", "dc-HN",
+            "LN-dc", "HN-end", "kj-sa", "kj-HN", "kj-dc"
+        ]);
+        let cave_system = CaveSystem::from_strings(&inputs).unwrap();
+        for encoded in vec!["v1", "v2", "require=kj,large=2"] {
+            let rule = parse_visit_rule(encoded).unwrap();
+            let mut streamed: Vec<String> = cave_system.paths(rule.as_ref())
+                .map(|p| p.ids(&cave_system))
+                .collect();
+            let mut enumerated = cave_system.compute_all_paths(rule.as_ref()).repr();
+            streamed.sort();
+            enumerated.sort();
+            assert_eq!(streamed, enumerated);
+        }
+    }
+
+    #[test]
+    fn stream_paths_lazily() {
+        let inputs = strs_to_strings(&vec![
+            "start-A", "start-b", "A-c", "A-b", "b-d", "A-end", "b-end"
+        ]);
+        let cave_system = CaveSystem::from_strings(&inputs).unwrap();
+        let mut paths = cave_system.paths(&PathRestrictions::V2);
+        assert_eq!(paths.next().unwrap().ids(&cave_system), "start,A,c,A,c,A,b,A,end");
+        assert_eq!(paths.take(5).count(), 5);
+
+        let through_d: Vec<Path> = cave_system.paths(&PathRestrictions::V2)
+            .filter(|p| p.passes_through(&cave_system, "d"))
+            .collect();
+        assert_eq!(through_d.len(), 8);
+        assert!(through_d.iter().all(|p| p.passes_through(&cave_system, "start")));
+        assert!(!through_d[0].passes_through(&cave_system, "nowhere"));
+        let shortest = cave_system.paths(&PathRestrictions::V1).min_by_key(|p| p.len()).unwrap();
+        assert_eq!(shortest.ids(&cave_system), "start,A,end");
+    }
+
+    #[test]
+    fn write_paths_to_file() {
+        let inputs = strs_to_strings(&vec![
+            "start-A", "start-b", "A-c", "A-b", "b-d", "A-end", "b-end"
+        ]);
+        let cave_system = CaveSystem::from_strings(&inputs).unwrap();
+        let path = env::temp_dir().join(format!("cave-paths-test-{}", std::process::id()));
+        let path = path.to_str().unwrap();
+        assert_eq!(cave_system.write_paths(cave_system.paths(&PathRestrictions::V1), path).unwrap(), 10);
+        let mut written = read_lines_as_str_vector(path);
+        written.sort();
+        let mut expected = cave_system.compute_all_paths(&PathRestrictions::V1).repr();
+        expected.sort();
+        assert_eq!(written, expected);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn export_dot() {
+        let cave_system = CaveSystem::from_strings(&strs_to_strings(&vec!["start-A", "A-b", "A-end", "b-end"])).unwrap();
+        assert_eq!(
+            cave_system.to_dot(DotOverlay::Plain),
+            "digraph caves {\n\
+            \x20   \"A\" [shape=box, style=filled, fillcolor=lightblue];\n\
+            \x20   \"b\" [shape=circle];\n\
+            \x20   \"end\" [shape=doublecircle, style=filled, fillcolor=lightpink];\n\
+            \x20   \"start\" [shape=doublecircle, style=filled, fillcolor=palegreen];\n\
+            \x20   \"A\" -> \"b\";\n\
+            \x20   \"A\" -> \"end\";\n\
+            \x20   \"b\" -> \"A\";\n\
+            \x20   \"b\" -> \"end\";\n\
+            \x20   \"start\" -> \"A\";\n\
+            }\n"
+        );
+
+        let path = cave_system.paths(&PathRestrictions::V1)
+            .find(|p| p.ids(&cave_system) == "start,A,b,A,end")
+            .unwrap();
+        let highlighted = cave_system.to_dot(DotOverlay::Path(&path));
+        assert!(highlighted.contains("\"A\" [shape=box, style=filled, fillcolor=lightblue, color=red, penwidth=2];"));
+        assert!(highlighted.contains("\"b\" -> \"A\" [color=red, penwidth=2, label=\"3\"];"));
+        assert!(highlighted.contains("\"b\" -> \"end\";"));
+
+        let usage = cave_system.to_dot(DotOverlay::EdgeUsage(&PathRestrictions::V1));
+        assert!(usage.contains("\"start\" -> \"A\" [label=\"3\", penwidth=5.0];"));
+        assert!(usage.contains("\"A\" -> \"b\" [label=\"2\", penwidth=3.7];"));
+        assert!(usage.contains("\"b\" -> \"A\" [label=\"1\", penwidth=2.3];"));
+        assert_eq!(usage, cave_system.to_dot(DotOverlay::EdgeUsage(&PathRestrictions::V1)));
+    }
+
+    #[test]
+    fn analyse_cave_system() {
+        let inputs = strs_to_strings(&vec![
+            "start-A", "start-b", "A-c", "A-b", "b-d", "A-end", "b-end", "e-f", "end-g"
+        ]);
+        let cave_system = CaveSystem::from_strings(&inputs).unwrap();
+        let report = cave_system.analyse(&PathRestrictions::V1);
+        assert_eq!(report.n_paths, 10);
+        assert_eq!(report.unreachable_from_start, strs_to_strings(&vec!["e", "f", "g"]));
+        assert_eq!(report.cannot_reach_end, strs_to_strings(&vec!["e", "f"]));
+        assert_eq!(report.dead_ends, strs_to_strings(&vec!["d", "e", "f"]));
+        assert!(report.on_every_path.is_empty());
+
+        let cave = |name: &str| report.caves.iter().find(|c| c.cave == name).unwrap().clone();
+        assert_eq!(cave("start"), CaveUsage { cave: "start".to_string(), visits: 10, paths_through: 10 });
+        assert_eq!(cave("c").visits, 5);
+        assert_eq!(cave("d").paths_through, 0);
+        assert_eq!(cave("A").visits, 17);
+        assert_eq!(cave("A").paths_through, 9);
+        let total_from_start: u64 = report.connections.iter().filter(|c| c.start == "start").map(|c| c.paths).sum();
+        assert_eq!(total_from_start, 10);
+        assert_eq!(
+            report.connections.iter().find(|c| c.start == "b" && c.end == "d").unwrap().paths,
+            0
+        );
+        assert_eq!(report.repr()[0], "Valid paths: 10");
+        assert_eq!(report.repr()[2], "Cannot reach end: e, f");
+        let json = report.to_json();
+        assert!(json.starts_with("{\"n_paths\": 10, \"unreachable_from_start\": [\"e\", \"f\", \"g\"], "));
+        assert!(json.contains("\"on_every_path\": [], "));
+        assert!(json.contains("{\"cave\": \"start\", \"visits\": 10, \"paths_through\": 10}"));
+        assert!(json.contains("{\"start\": \"b\", \"end\": \"d\", \"paths\": 0}"));
+    }
+
+    #[test]
+    fn analyse_bottleneck_caves() {
+        let inputs = strs_to_strings(&vec!["start-a", "a-B", "a-c", "B-d", "c-d", "d-end"]);
+        let report = CaveSystem::from_strings(&inputs).unwrap().analyse(&PathRestrictions::V2);
+        assert_eq!(report.on_every_path, strs_to_strings(&vec!["a", "d"]));
+        assert!(report.unreachable_from_start.is_empty());
+        assert!(report.cannot_reach_end.is_empty());
+        assert_eq!(report.repr()[4], "Caves on every path: a, d");
+
+        // The way round b through c is there, but the rule never takes it
+        let inputs = strs_to_strings(&vec!["start-a", "a-b", "b-end", "a-c", "c-end"]);
+        let cave_system = CaveSystem::from_strings(&inputs).unwrap();
+        assert_eq!(cave_system.analyse(&PathRestrictions::V1).on_every_path, strs_to_strings(&vec!["a"]));
+        let rules = RuleSet::parse("forbid=c").unwrap();
+        assert_eq!(cave_system.analyse(&rules).on_every_path, strs_to_strings(&vec!["a", "b"]));
+
+        let mut cave_system = CaveSystem::new();
+        cave_system.add_connection(Connection::new(&"start".to_string(), &"a".to_string()));
+        let empty = cave_system.analyse(&PathRestrictions::V1);
+        assert_eq!(empty.n_paths, 0);
+        assert_eq!(empty.cannot_reach_end, strs_to_strings(&vec!["a", "start"]));
+    }
+
+    #[test]
+    fn parse_weighted_connections() {
+        let cave_system = CaveSystem::from_strings(&strs_to_strings(&vec![
+            "start-A:2", "A-b: 5", "A-end", "end-b:3"
+        ])).unwrap();
+        let (a, b) = (cave_system.graph.node_id("A").unwrap(), cave_system.graph.node_id("b").unwrap());
+        let (start, end) = (cave_system.graph.node_id("start").unwrap(), cave_system.graph.node_id("end").unwrap());
+        assert_eq!(cave_system.graph.edge_weight(start, a), Some(&2));
+        assert_eq!(cave_system.graph.edge_weight(a, b), Some(&5));
+        assert_eq!(cave_system.graph.edge_weight(b, a), Some(&5));
+        assert_eq!(cave_system.graph.edge_weight(a, end), Some(&1));
+        assert_eq!(cave_system.graph.edge_weight(b, end), Some(&3));
+
+        let error = |lines: Vec<&str>| CaveSystem::from_strings(&strs_to_strings(&lines)).unwrap_err();
+        assert_eq!(error(vec!["start-a:x", "a-end"]), "Line 1: weight \"x\" is not a non-negative integer");
+        assert_eq!(error(vec!["start-a", "a-end:-1"]), "Line 2: weight \"-1\" is not a non-negative integer");
+    }
+
+    #[test]
+    fn cheapest_and_most_expensive_paths() {
+        let cave_system = CaveSystem::from_strings(&strs_to_strings(&vec![
+            "start-A:4", "start-b", "A-c:2", "A-b:3", "b-d", "A-end:6", "b-end:9"
+        ])).unwrap();
+        let costs: Vec<(String, u64)> = cave_system.paths(&PathRestrictions::V1)
+            .map(|p| (p.ids(&cave_system), p.cost))
+            .collect();
+        assert_eq!(costs.len(), 10);
+        assert!(costs.contains(&("start,A,end".to_string(), 10)));
+        assert!(costs.contains(&("start,b,A,c,A,end".to_string(), 14)));
+
+        let cheapest = cave_system.cheapest_path(&PathRestrictions::V1).unwrap();
+        assert_eq!((cheapest.ids(&cave_system), cheapest.cost), ("start,A,end".to_string(), 10));
+        let most_expensive = cave_system.most_expensive_path(&PathRestrictions::V1).unwrap();
+        assert_eq!(
+            (most_expensive.ids(&cave_system), most_expensive.cost),
+            ("start,A,b,A,c,A,end".to_string(), 20)
+        );
+        assert_eq!(most_expensive.cost, costs.iter().map(|(_, c)| *c).max().unwrap());
+        let v2_most_expensive = cave_system.most_expensive_path(&PathRestrictions::V2).unwrap();
+        assert!(v2_most_expensive.cost > most_expensive.cost);
+        assert_eq!(cave_system.cheapest_path(&RuleSet::parse("forbid=b|end").unwrap()), None);
+    }
+
+    #[test]
+    fn paths_within_budget() {
+        let cave_system = CaveSystem::from_strings(&strs_to_strings(&vec![
+            "start-A:4", "start-b", "A-c:2", "A-b:3", "b-d", "A-end:6", "b-end:9"
+        ])).unwrap();
+        for budget in vec![0, 10, 14, 100] {
+            let mut within: Vec<String> = cave_system.paths_within(&PathRestrictions::V2, budget)
+                .map(|p| p.ids(&cave_system))
+                .collect();
+            let mut expected: Vec<String> = cave_system.paths(&PathRestrictions::V2)
+                .filter(|p| p.cost <= budget)
+                .map(|p| p.ids(&cave_system))
+                .collect();
+            within.sort();
+            expected.sort();
+            assert_eq!(within, expected);
+        }
+        assert_eq!(cave_system.paths_within(&PathRestrictions::V1, 10).count(), 3);
+        assert_eq!(cave_system.paths_within(&PathRestrictions::V2, 100).count(), 36);
     }
 }
@@ -1,4 +1,5 @@
 use std::collections::HashSet;
+use super::sparse_grid::SparseGrid;
 use super::utils::lines_into_chunks;
 use super::utils::read_lines_as_str_vector;
 
@@ -59,20 +60,14 @@ impl Paper {
     }
 
     fn repr(&self) -> Vec<String> {
-        let mut to_sort: Vec<(u32, u32)> = self.dots.iter()
-            .map(|r| r.clone()).collect();
-        to_sort.sort();
-        let max_x = to_sort.last().unwrap().0;
-        let max_y = to_sort.last().unwrap().1;
-        return (0..=max_y).map(|y|
-            (0..=max_x).map(|x| (x, y))
-                .map(
-                    |pair| {
-                        if self.dots.contains(&pair) { "#" }
-                        else { "." }
-                    }
-                ).collect::<Vec<&str>>().join("")
-            ).collect()
+        let mut grid = SparseGrid::new();
+        for &(x, y) in &self.dots {
+            grid.insert(x as i32, y as i32, ());
+        }
+        let mut bbox = grid.bounding_box().unwrap();
+        bbox.min_x = 0;
+        bbox.min_y = 0;
+        return grid.render_within(&bbox, '.', |_| '#')
     }
 }
 
@@ -1,40 +1,28 @@
 use std::collections::HashSet;
+use super::distance::Blocked;
 use super::matrix::Matrix;
-
-#[derive(Clone, Eq, PartialEq, Hash, Debug)]
-struct Point {
-    x: usize,
-    y: usize,
-    risk_level: u32
-}
-
-impl Point {
-    fn new(x: usize, y: usize) -> Point {
-        return Point { x, y, risk_level: 0 }
-    }
-}
+use super::movement::MovementModel;
+use super::point::Point2;
+use super::render::Palette;
+use super::utils::read_lines_as_str_vector;
 
 #[derive(Clone, Eq, PartialEq, Hash, Debug)]
 struct Path {
-    steps: Vec<Point>,
-    total_risk: u32
+    steps: Vec<Point2>,
+    total_risk: u64
 }
 
 impl Path {
-    fn new(start: Point) -> Path {
-        return Path { steps: vec![start], total_risk: 0 }
+    fn first(&self) -> Point2 {
+        return *self.steps.first().unwrap()
     }
 
-    fn from_vec(steps: &Vec<Point>) -> Path {
-        return Path { steps: steps.clone(), total_risk: 0 }
+    fn last(&self) -> Point2 {
+        return *self.steps.last().unwrap()
     }
 
-    fn first(&self) -> &Point {
-        return self.steps.first().unwrap()
-    }
-
-    fn last(&self) -> &Point {
-        return self.steps.last().unwrap()
+    fn cells(&self) -> Vec<(i32, i32)> {
+        return self.steps.iter().map(|p| (*p).into()).collect()
     }
 
     fn repr(&self) -> String {
@@ -45,85 +33,226 @@ impl Path {
 
 struct Cavern {
     risk_levels: Matrix<u32>,
+    movement: MovementModel<u32>
 }
 
 impl Cavern {
-    const MAX_ITER: usize = 5;
-
     fn new(rows: &Vec<String>) -> Cavern {
         let risk_levels = Matrix::from_lines(rows);
-        return Cavern { risk_levels }
+        return Cavern { risk_levels, movement: MovementModel::orthogonal() }
     }
 
-    fn find_lowest_risk_path_brute_force(&self) -> Path {
-        let path = Path::new(Point::new(0, 0));
-        return path
+    fn with_movement(mut self, movement: MovementModel<u32>) -> Cavern {
+        self.movement = movement;
+        return self
     }
 
-    fn simulate_paths_brute_force(&self) -> Vec<Path> {
-        let start_x = 0;
-        let start_y = 0;
-        let start_path = Path::new(Point::new(start_x, start_y));
-        let mut paths = vec![start_path];
-        Cavern::_simulate_paths_brute_force(
-            self, &mut paths, start_x, start_y, 0);
-        return paths
+    // The map repeated `factor` times in both directions, every repetition to the
+    // right or downwards adds one to the risk levels, wrapping from 9 back to 1
+    fn tiled(&self, factor: usize) -> Cavern {
+        let (n_rows, n_columns) = (self.risk_levels.n_rows(), self.risk_levels.n_columns());
+        let rows = (0..(n_rows * factor)).map(|y|
+            (0..(n_columns * factor)).map(|x| {
+                let risk = self.risk_levels.row(y % n_rows)[x % n_columns] + (x / n_columns + y / n_rows) as u32;
+                (risk - 1) % 9 + 1
+            }).collect()
+        ).collect();
+        return Cavern { risk_levels: Matrix::from_rows(rows), movement: self.movement.clone() }
     }
 
-    fn _simulate_paths_brute_force(
-        &self,
-        paths: &mut Vec<Path>,
-        target_x: usize,
-        target_y: usize,
-        iter_id: usize
-    ) {
-        if iter_id > Cavern::MAX_ITER {
-            panic!("Limit reached. Iter ID={}", iter_id)
-        }
+    fn risk_level(&self, position: Point2) -> Option<u32> {
+        return self.risk_levels.get(position)
+    }
 
-        let ns = Cavern::_find_neighbours(
-            self, target_x, target_y);
-
-        if ns.len() == 0 { return }
-
-        let mut paths_new = Vec::new();
-        for p in &paths.clone() {
-            let last = p.last();
-            let neighbours = Cavern::_find_neighbours(
-                self, last.x, last.y);
-
-            for neighbour in &neighbours {
-                let mut new_p_points = p.clone().steps;
-                new_p_points.push(neighbour.clone());
-                paths_new.push(Path::from_vec(&new_p_points));
-                Cavern::_simulate_paths_brute_force(
-                    self,
-                    &mut paths_new,
-                    neighbour.x.clone(),
-                    neighbour.y.clone(),
-                    iter_id + 1
-                );
-            }
+    // Every consecutive pair of cells has to be a move allowed by the movement model
+    fn _path(&self, cells: &[Point2]) -> Path {
+        let total_risk = cells.windows(2).map(|step| {
+            let (from, to) = (step[0], step[1]);
+            self.movement.successors(&self.risk_levels, from.x, from.y).into_iter()
+                .filter(|(next, _)| Point2::from(*next) == to)
+                .map(|(_, cost)| cost)
+                .min()
+                .unwrap_or_else(|| panic!("No move from {:?} to {:?}", from, to))
+        }).sum();
+        return Path { steps: cells.to_vec(), total_risk }
+    }
+
+    fn entrance(&self) -> Point2 {
+        return Point2::new(0, 0)
+    }
+
+    fn exit(&self) -> Point2 {
+        return Point2::from((self.risk_levels.n_columns(), self.risk_levels.n_rows())) - Point2::new(1, 1)
+    }
+
+    // None when the movement model leaves no way from the entrance to the exit,
+    // or when the cavern is empty
+    fn lowest_risk_path_dijkstra(&self) -> Option<Path> {
+        return self._search(self.entrance(), self.exit(), &Blocked::default(), false)
+    }
+
+    // Falls back to plain Dijkstra unless the movement model is the orthogonal one
+    fn lowest_risk_path_a_star(&self) -> Option<Path> {
+        return self._search(self.entrance(), self.exit(), &Blocked::default(), true)
+    }
+
+    fn lowest_risk_path_avoiding(&self, blocked_cells: &HashSet<Point2>) -> Option<Path> {
+        let blocked = Blocked { cells: blocked_cells.iter().map(|cell| (*cell).into()).collect(), moves: HashSet::new() };
+        return self._search(self.entrance(), self.exit(), &blocked, true)
+    }
+
+    // Waypoints are visited in the given order. Every leg is the cheapest on its own,
+    // so legs may cross each other or walk back over earlier cells. None when a
+    // waypoint lies outside the cavern.
+    fn lowest_risk_path_through(&self, waypoints: &[Point2]) -> Option<Path> {
+        if waypoints.iter().any(|waypoint| !self.risk_levels.contains(*waypoint)) { return None }
+        let mut stops = vec![self.entrance()];
+        stops.extend_from_slice(waypoints);
+        stops.push(self.exit());
+        let mut cells = vec![self.entrance()];
+        for leg in stops.windows(2) {
+            let path = self._search(leg[0], leg[1], &Blocked::default(), true)?;
+            cells.extend(path.steps.into_iter().skip(1));
         }
-        *paths = paths_new;
+        return Some(self._path(&cells))
     }
 
-    fn _find_neighbours(&self, target_x: usize, target_y: usize) -> HashSet<Point> {
-        let x_ = target_x as i32;
-        let y_ = target_y as i32;
-        let indexes_to_find = [
-            (x_ + 1, y_),
-            (x_, y_ + 1)
-        ];
+    // Yen's algorithm: the K cheapest paths which never visit a cell twice, in order
+    // of increasing risk (ties are broken by the cells visited)
+    fn k_lowest_risk_paths(&self, k: usize) -> Vec<Path> {
+        let mut found: Vec<Path> = Vec::new();
+        let mut candidates: Vec<Path> = Vec::new();
+        match self.lowest_risk_path_dijkstra() {
+            Some(path) if k > 0 => found.push(path),
+            _ => return found
+        }
+
+        while found.len() < k {
+            let last = found.last().unwrap().steps.clone();
+            for i in 0..(last.len() - 1) {
+                let root = &last[..=i];
+                let mut blocked = Blocked::default();
+                for path in &found {
+                    let cells = &path.steps;
+                    if cells.len() > i + 1 && &cells[..=i] == root {
+                        blocked.moves.insert((cells[i].into(), cells[i + 1].into()));
+                    }
+                }
+                blocked.cells.extend(root[..i].iter().map(|cell| <(i32, i32)>::from(*cell)));
 
-        let mut neighbours = HashSet::new();
-        for pair in indexes_to_find {
-            if self.risk_levels.index_exists(pair.0, pair.1) {
-                neighbours.insert(Point::new(pair.0 as usize, pair.1 as usize));
+                if let Some(spur) = self._search(last[i], self.exit(), &blocked, true) {
+                    let mut cells = root.to_vec();
+                    cells.extend(spur.steps.into_iter().skip(1));
+                    let candidate = self._path(&cells);
+                    if !candidates.contains(&candidate) && !found.contains(&candidate) {
+                        candidates.push(candidate)
+                    }
+                }
             }
+            if candidates.is_empty() { break }
+            let best = (0..candidates.len())
+                .min_by_key(|idx| (candidates[*idx].total_risk, candidates[*idx].steps.clone()))
+                .unwrap();
+            found.push(candidates.swap_remove(best));
         }
+        return found
+    }
+
+    // Moves and their costs come from the movement model, the risk of the starting
+    // position is not counted. No move costs less than the lowest risk level, so with
+    // `informed` set the search may use the Manhattan distance to the target times
+    // that level as an A* heuristic. Caverns holding a 0 are thus searched by Dijkstra.
+    fn _search(&self, from: Point2, to: Point2, blocked: &Blocked, informed: bool) -> Option<Path> {
+        let min_risk = if informed { self.risk_levels.points_iter().min().map(u64::from) } else { None };
+        let (total_risk, cells) = self.risk_levels.shortest_path(from.into(), to.into(), &self.movement, blocked, min_risk)?;
+        return Some(Path { steps: cells.into_iter().map(Point2::from).collect(), total_risk })
+    }
+
+    fn path_to_dot(&self, path: &Path) -> String {
+        return self.risk_levels.path_to_dot(&Palette::heat(), &path.cells())
+    }
+
+    fn path_to_svg(&self, path: &Path, cell_size: usize) -> String {
+        return self.risk_levels.path_to_svg(&Palette::heat(), &path.cells(), cell_size)
+    }
+}
+
+pub fn day_15() {
+    let filename = "data/day-15-chiton/data-part-1.txt";
+    let cavern = Cavern::new(&read_lines_as_str_vector(filename));
+    match cavern.lowest_risk_path_dijkstra() {
+        Some(path) => println!("Day 15 Part 1 result: {res}", res=path.total_risk),
+        None => println!("Day 15 Part 1: the exit cannot be reached")
+    }
+    match cavern.tiled(5).lowest_risk_path_a_star() {
+        Some(path) => println!("Day 15 Part 2 result: {res}", res=path.total_risk),
+        None => println!("Day 15 Part 2: the exit cannot be reached")
+    }
+}
+
+// "x,y", as the chiton command takes cells
+fn parse_cell(encoded: &str) -> Result<Point2, String> {
+    let coordinates: Result<Vec<i32>, _> = encoded.split(',').map(|c| c.trim().parse::<i32>()).collect();
+    return match coordinates.as_ref().map(|c| c.as_slice()) {
+        Ok([x, y]) => Ok(Point2::new(*x, *y)),
+        _ => Err(format!("{:?} is not a cell of the form x,y", encoded))
+    }
+}
 
-        return neighbours
+// Usage: chiton <input-file> [tiles] [movement] [text|dot|svg] [query], prints the
+// total risk from the top left to the bottom right of the cavern repeated `tiles`
+// times (1 by default), the path taken and the risk level of every cell on it. The
+// movement model is one `MovementModel::parse` accepts, orthogonal by default. The
+// query picks the paths:
+//   lowest                the cheapest one, the default
+//   avoid x,y x,y ...     the cheapest one never entering the given cells
+//   through x,y x,y ...   the cheapest one visiting the given cells in order
+//   top K                 the K cheapest ones never visiting a cell twice
+// With dot or svg the last path found is drawn over the cavern, as a Graphviz graph
+// (render with `neato -n`) or an SVG image.
+pub fn chiton_command(args: &[String]) {
+    let usage = "Usage: chiton <input-file> [tiles] [movement] [text|dot|svg] [lowest|avoid|through|top] [cells|K]";
+    let filename = match args.first() {
+        Some(filename) => filename,
+        None => panic!("{}", usage)
+    };
+    let tiles = args.get(1).map(|t| t.parse::<usize>().expect(usage)).unwrap_or(1);
+    if tiles == 0 {
+        panic!("{}", usage)
+    }
+    let movement = MovementModel::parse(args.get(2).map(|m| m.as_str()).unwrap_or("orthogonal"))
+        .unwrap_or_else(|e| panic!("{}", e));
+    let cavern = Cavern::new(&read_lines_as_str_vector(filename)).tiled(tiles).with_movement(movement);
+    let query_args = args.get(5..).unwrap_or(&[]);
+    let cells = || query_args.iter().map(|c| parse_cell(c)).collect::<Result<Vec<Point2>, String>>()
+        .unwrap_or_else(|e| panic!("{}", e));
+    let paths: Vec<Path> = match args.get(4).map(|q| q.as_str()).unwrap_or("lowest") {
+        "lowest" => cavern.lowest_risk_path_a_star().into_iter().collect(),
+        "avoid" => cavern.lowest_risk_path_avoiding(&cells().into_iter().collect()).into_iter().collect(),
+        "through" => cavern.lowest_risk_path_through(&cells()).into_iter().collect(),
+        "top" => match query_args {
+            [k] => cavern.k_lowest_risk_paths(k.parse::<usize>().expect(usage)),
+            _ => panic!("{}", usage)
+        },
+        query => panic!("Unknown query {:?}, expected lowest, avoid, through or top", query)
+    };
+    let path = match paths.last() {
+        Some(path) => path,
+        None => panic!("No path through the cavern in {} answers the query", filename)
+    };
+    match args.get(3).map(|f| f.as_str()).unwrap_or("text") {
+        "text" => for path in &paths {
+            let (first, last) = (path.first(), path.last());
+            println!("Total risk from ({},{}) to ({},{}): {}", first.x, first.y, last.x, last.y, path.total_risk);
+            println!("Path: {}", path.repr());
+            let risks: Vec<String> = path.steps.iter()
+                .map(|step| cavern.risk_level(*step).unwrap().to_string())
+                .collect();
+            println!("Risk levels: {}", risks.join(","))
+        },
+        "dot" => print!("{}", cavern.path_to_dot(path)),
+        "svg" => print!("{}", cavern.path_to_svg(path, 10)),
+        format => panic!("Unknown output format {:?}, expected text, dot or svg", format)
     }
 }
 
@@ -151,16 +280,214 @@ mod tests {
         assert_eq!(cavern.risk_levels.n_columns(), 10);
     }
 
+    fn example() -> Cavern {
+        return Cavern::new(&read_lines_as_str_vector("data/day-15-chiton/data-test.txt"))
+    }
+
     #[test]
-    fn test_simulate_paths_brute_force() {
-        let input = strs_to_strings(&vec![
-            "116",
-            "138"
-        ]);
-        let cavern = Cavern::new(&input);
-        let paths = cavern.simulate_paths_brute_force();
-        for p in paths {
-            println!("{}", p.repr())
+    fn test_lowest_risk_path() {
+        let cavern = example();
+        let path = cavern.lowest_risk_path_dijkstra().unwrap();
+        assert_eq!(path.total_risk, 40);
+        assert_eq!(path.first(), Point2::new(0, 0));
+        assert_eq!(path.last(), Point2::new(9, 9));
+        assert_eq!(path.steps.len(), 19);
+        assert_eq!(cavern.lowest_risk_path_a_star().unwrap().total_risk, 40);
+        let distances = cavern.risk_levels.distance_map((0, 0), &cavern.movement);
+        assert_eq!(distances.distance(9, 9), Some(40));
+        assert_eq!(distances.path_to(9, 9).unwrap(), path.cells());
+    }
+
+    #[test]
+    fn test_zero_risk_levels() {
+        let cavern = Cavern::new(&strs_to_strings(&["09000", "09090", "00010"]));
+        assert_eq!(cavern.lowest_risk_path_dijkstra().unwrap().total_risk, 0);
+        assert_eq!(cavern.lowest_risk_path_a_star().unwrap().total_risk, 0);
+        assert_eq!(cavern.lowest_risk_path_avoiding(&HashSet::new()).unwrap().total_risk, 0);
+    }
+
+    #[test]
+    fn test_empty_cavern() {
+        let empty = Cavern { risk_levels: Matrix::from_rows(Vec::new()), movement: MovementModel::orthogonal() };
+        assert_eq!(empty.lowest_risk_path_dijkstra(), None);
+        assert_eq!(empty.lowest_risk_path_a_star(), None);
+        assert!(empty.k_lowest_risk_paths(2).is_empty());
+    }
+
+    #[test]
+    fn test_path_steps_are_adjacent() {
+        let cavern = example();
+        let path = cavern.lowest_risk_path_a_star().unwrap();
+        for step in path.steps.windows(2) {
+            assert_eq!(step[0].manhattan(&step[1]), 1);
+        }
+        assert_eq!(path_risk(&cavern, &path), path.total_risk);
+    }
+
+    #[test]
+    fn test_path_moving_up_and_left() {
+        let cavern = Cavern::new(&strs_to_strings(&vec![
+            "19111",
+            "19191",
+            "11191",
+            "99991",
+            "99991"
+        ]));
+        let path = cavern.lowest_risk_path_dijkstra().unwrap();
+        assert_eq!(path.total_risk, 12);
+        assert_eq!(cavern.lowest_risk_path_a_star(), Some(path.clone()));
+        assert_eq!(
+            path.repr(),
+            "(0,0)->(0,1)->(0,2)->(1,2)->(2,2)->(2,1)->(2,0)->(3,0)->(4,0)->(4,1)->(4,2)->(4,3)->(4,4)"
+        );
+    }
+
+    #[test]
+    fn test_tiled_map() {
+        let tiled = example().tiled(5);
+        assert_eq!(tiled.risk_levels.n_rows(), 50);
+        assert_eq!(tiled.risk_levels.n_columns(), 50);
+        assert_eq!(tiled.risk_levels.row(0)[10], 2);
+        assert_eq!(tiled.risk_levels.row(49)[49], 9);
+        assert_eq!(tiled.lowest_risk_path_dijkstra().unwrap().total_risk, 315);
+        assert_eq!(tiled.lowest_risk_path_a_star().unwrap().total_risk, 315);
+
+        let single = Cavern::new(&strs_to_strings(&vec!["8"])).tiled(5);
+        assert_eq!(single.risk_levels.row(0), &[8, 9, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_export_path() {
+        let cavern = Cavern::new(&strs_to_strings(&vec!["116", "138"]));
+        let path = cavern._path(&[Point2::new(0, 0), Point2::new(0, 1), Point2::new(1, 1)]);
+        let dot = cavern.path_to_dot(&path);
+        assert!(dot.contains("\"0,1\" -> \"1,1\" [color=\"#ff0000\", penwidth=2];"));
+        assert_eq!(dot.matches("penwidth=3").count(), 3);
+        let svg = cavern.path_to_svg(&path, 10);
+        assert_eq!(svg.matches("<rect").count(), 6);
+        assert!(svg.contains("<polyline points=\"5,5 5,15 15,15\""));
+    }
+
+    // Sum of the risk levels entered along the path
+    fn path_risk(cavern: &Cavern, path: &Path) -> u64 {
+        return path.steps.iter().skip(1).map(|p| cavern.risk_level(*p).unwrap() as u64).sum()
+    }
+
+    // Risks of every path which never visits a cell twice, cheapest first
+    fn all_simple_path_risks(cavern: &Cavern) -> Vec<u64> {
+        fn walk(cavern: &Cavern, position: Point2, risk: u64, seen: &mut HashSet<Point2>, risks: &mut Vec<u64>) {
+            if position == cavern.exit() { return risks.push(risk) }
+            for (next, level) in cavern.risk_levels.neighbours(position.x, position.y, false) {
+                let next = Point2::from(next);
+                if !seen.insert(next) { continue }
+                walk(cavern, next, risk + level as u64, seen, risks);
+                seen.remove(&next);
+            }
         }
+        let mut risks = Vec::new();
+        walk(cavern, cavern.entrance(), 0, &mut vec![cavern.entrance()].into_iter().collect(), &mut risks);
+        risks.sort();
+        return risks
+    }
+
+    #[test]
+    fn test_k_lowest_risk_paths() {
+        let cavern = Cavern::new(&strs_to_strings(&vec!["1163", "1381", "2136", "3694"]));
+        let expected = all_simple_path_risks(&cavern);
+        let paths = cavern.k_lowest_risk_paths(12);
+        assert_eq!(paths.len(), 12);
+        assert_eq!(paths.iter().map(|p| p.total_risk).collect::<Vec<u64>>(), expected[..12].to_vec());
+        for (i, path) in paths.iter().enumerate() {
+            assert_eq!(path.first(), Point2::new(0, 0));
+            assert_eq!(path.last(), Point2::new(3, 3));
+            let cells: HashSet<Point2> = path.steps.iter().cloned().collect();
+            assert_eq!(cells.len(), path.steps.len());
+            assert!(!paths[..i].contains(path));
+        }
+
+        let all = Cavern::new(&strs_to_strings(&vec!["12", "34"])).k_lowest_risk_paths(5);
+        assert_eq!(all.iter().map(|p| p.repr()).collect::<Vec<String>>(), vec![
+            "(0,0)->(1,0)->(1,1)",
+            "(0,0)->(0,1)->(1,1)"
+        ]);
+        assert_eq!(example().k_lowest_risk_paths(3)[0].total_risk, 40);
+        assert!(example().k_lowest_risk_paths(0).is_empty());
+    }
+
+    #[test]
+    fn test_parse_cell() {
+        assert_eq!(parse_cell("3,4"), Ok(Point2::new(3, 4)));
+        assert_eq!(parse_cell(" -1, 0"), Ok(Point2::new(-1, 0)));
+        assert!(parse_cell("3").is_err());
+        assert!(parse_cell("3,4,5").is_err());
+        assert!(parse_cell("a,b").is_err());
+    }
+
+    #[test]
+    fn test_lowest_risk_path_through_waypoints() {
+        let cavern = example();
+        let path = cavern.lowest_risk_path_through(&[Point2::new(9, 0), Point2::new(0, 9)]).unwrap();
+        assert!(path.steps.contains(&Point2::new(9, 0)));
+        assert!(path.steps.contains(&Point2::new(0, 9)));
+        assert_eq!(path.first(), Point2::new(0, 0));
+        assert_eq!(path.last(), Point2::new(9, 9));
+        assert_eq!(path_risk(&cavern, &path), path.total_risk);
+        assert_eq!(cavern.lowest_risk_path_through(&[]).unwrap().total_risk, 40);
+        assert_eq!(cavern.lowest_risk_path_through(&[Point2::new(0, 0)]).unwrap().total_risk, 40);
+        assert_eq!(cavern.lowest_risk_path_through(&[Point2::new(10, 3)]), None);
+        assert_eq!(cavern.lowest_risk_path_through(&[Point2::new(2, 2), Point2::new(-1, 0)]), None);
+    }
+
+    #[test]
+    fn test_lowest_risk_path_avoiding_cells() {
+        let cavern = Cavern::new(&strs_to_strings(&vec!["111", "191", "111"]));
+        let blocked: HashSet<Point2> = vec![Point2::new(1, 0)].into_iter().collect();
+        let path = cavern.lowest_risk_path_avoiding(&blocked).unwrap();
+        assert_eq!(path.repr(), "(0,0)->(0,1)->(0,2)->(1,2)->(2,2)");
+        assert_eq!(path.total_risk, 4);
+
+        let walled: HashSet<Point2> = vec![Point2::new(1, 0), Point2::new(0, 1)].into_iter().collect();
+        assert_eq!(cavern.lowest_risk_path_avoiding(&walled), None);
+        let exit: HashSet<Point2> = vec![Point2::new(2, 2)].into_iter().collect();
+        assert_eq!(cavern.lowest_risk_path_avoiding(&exit), None);
+    }
+
+    #[test]
+    fn test_movement_models() {
+        let cavern = Cavern::new(&strs_to_strings(&vec!["191", "919", "191"]));
+        assert_eq!(cavern.lowest_risk_path_dijkstra().unwrap().total_risk, 20);
+
+        let diagonal = Cavern::new(&strs_to_strings(&vec!["191", "919", "191"]))
+            .with_movement(MovementModel::eight_way(2));
+        let path = diagonal.lowest_risk_path_dijkstra().unwrap();
+        assert_eq!(path.repr(), "(0,0)->(1,1)->(2,2)");
+        assert_eq!(path.total_risk, 4);
+        assert_eq!(diagonal.lowest_risk_path_a_star(), Some(path.clone()));
+        assert_eq!(diagonal.k_lowest_risk_paths(2)[1].total_risk, 12);
+
+        let rows = strs_to_strings(&vec!["13111", "13131", "11131"]);
+        assert_eq!(Cavern::new(&rows).lowest_risk_path_dijkstra().unwrap().total_risk, 8);
+        let walled = Cavern::new(&rows)
+            .with_movement(MovementModel::orthogonal().with_walls(|v: &u32| *v == 3));
+        let path = walled.lowest_risk_path_dijkstra().unwrap();
+        assert_eq!(path.total_risk, 10);
+        assert!(path.steps.iter().all(|p| walled.risk_level(*p) == Some(1)));
+        let sealed = Cavern::new(&strs_to_strings(&vec!["19", "91"]))
+            .with_movement(MovementModel::orthogonal().with_walls(|v: &u32| *v == 9));
+        assert_eq!(sealed.lowest_risk_path_avoiding(&HashSet::new()), None);
+        assert_eq!(sealed.lowest_risk_path_dijkstra(), None);
+        assert_eq!(sealed.lowest_risk_path_a_star(), None);
+        assert!(sealed.k_lowest_risk_paths(3).is_empty());
+
+        let gentle = Cavern::new(&strs_to_strings(&vec!["159", "234", "999"]))
+            .with_movement(MovementModel::orthogonal()
+                .with_entry_cost(|from: &u32, to: &u32| Some((*to as i64 - *from as i64).unsigned_abs() + 1)));
+        let path = gentle.lowest_risk_path_dijkstra().unwrap();
+        assert_eq!(path.repr(), "(0,0)->(0,1)->(1,1)->(2,1)->(2,2)");
+        assert_eq!(path.total_risk, 2 + 2 + 2 + 6);
+
+        let knight = Cavern::new(&strs_to_strings(&vec!["111", "111", "111"]))
+            .with_movement(MovementModel::knight());
+        assert_eq!(knight.lowest_risk_path_dijkstra().unwrap().total_risk, 4);
     }
 }
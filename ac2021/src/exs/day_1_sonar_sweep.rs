@@ -1,51 +1,12 @@
 use super::utils::read_lines_as_vector;
+use super::window::{compare_windows, Aggregate};
 
 fn count_increases(measurements: &Vec<i32>) -> i32 {
-    let mut previous: i32 = 0;
-    let mut cnt: i32 = 0;
-    let mut first_is_processed: bool = false;
-
-    for measurement in measurements {
-        if first_is_processed {
-            if *measurement > previous {
-                cnt += 1
-            }
-        } else {
-            first_is_processed = true
-        }
-        previous = *measurement;
-    }
-    return cnt;
+    return compare_windows(measurements.iter().copied(), 1, Aggregate::Sum).increases as i32;
 }
 
 fn count_increases_sliding_window(measurements: &Vec<i32>) -> i32 {
-    let mut cnt: i32 = 0;
-
-    let mut pos: i32 = 0;
-    let window_size: i32 = 3;
-    for _measurement in measurements {
-        let previous_window_lower_bound = pos - window_size;
-        let previous_window_upper_bound = pos;
-        let current_window_lower_bound = previous_window_lower_bound + 1;
-        let current_window_upper_bound = previous_window_upper_bound + 1;
-
-        if previous_window_lower_bound >= 0 {
-
-            let previous_window = &measurements[
-                previous_window_lower_bound as usize..previous_window_upper_bound as usize];
-
-            let current_window = &measurements[
-                current_window_lower_bound as usize..current_window_upper_bound as usize];
-
-            let previous_window_sum: i32 = previous_window.iter().sum();
-            let current_window_sum: i32 = current_window.iter().sum();
-
-            if current_window_sum > previous_window_sum { cnt += 1 }
-        }
-        pos += 1;
-        if pos > current_window_upper_bound { break; }
-    }
-    return cnt;
+    return compare_windows(measurements.iter().copied(), 3, Aggregate::Sum).increases as i32;
 }
 
 pub fn day_1() {
@@ -60,6 +21,29 @@ pub fn day_1() {
     println!("Day 1 Part 2 result: {res}", res=result);
 }
 
+// Usage: sonar-windows <input-file> [window-size] [sum|mean|min|max|median], compares
+// every window aggregate of the depths with the one before
+pub fn sonar_windows_command(args: &[String]) {
+    let usage = "Usage: sonar-windows <input-file> [window-size] [sum|mean|min|max|median]";
+    let filename = match args.first() {
+        Some(filename) => filename,
+        None => panic!("{}", usage)
+    };
+    let size = args.get(1).map(|s| s.parse::<usize>().expect(usage)).unwrap_or(3);
+    if size == 0 {
+        panic!("{}", usage)
+    }
+    let name = args.get(2).map(|a| a.as_str()).unwrap_or("sum");
+    let aggregate = Aggregate::parse(name).unwrap_or_else(|e| panic!("{}", e));
+    let measurements: Vec<i32> = read_lines_as_vector(filename);
+    let comparison = compare_windows(measurements.iter().copied(), size, aggregate);
+    println!(
+        "Windows of {} by {}: {} increases, {} decreases, {} unchanged",
+        size, name, comparison.increases, comparison.decreases, comparison.unchanged
+    );
+    println!("Longest increasing run: {}", comparison.longest_increasing_run);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -75,4 +59,14 @@ mod tests {
         let input = vec![199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
         assert_eq!(count_increases_sliding_window(&input), 5);
     }
+
+    #[test]
+    fn test_compare_aggregates() {
+        let input = vec![199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+        let mean = compare_windows(input.iter().copied(), 3, Aggregate::Mean);
+        assert_eq!(mean, compare_windows(input.iter().copied(), 3, Aggregate::Sum));
+        let median = compare_windows(input.iter().copied(), 3, Aggregate::Median);
+        assert_eq!((median.increases, median.decreases, median.unchanged), (4, 1, 2));
+        assert_eq!(compare_windows(input.iter().copied(), 2, Aggregate::Max).longest_increasing_run, 2);
+    }
 }
@@ -1,38 +1,501 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use super::point::{Direction, Point3};
 use super::utils::read_lines_as_str_vector;
 
-fn calc_final_pos_and_depth(movements: &Vec<String>) -> (i32, i32) {
-    let mut horizontal_position: i32 = 0;
-    let mut depth: i32 = 0;
-    for movement in movements {
-        let (command, coordinate) = parse_into_movement(movement);
-        if command.eq("forward") { horizontal_position += coordinate }
-        if command.eq("down") { depth += coordinate }
-        if command.eq("up") {
-            depth -= coordinate;
-            if depth < 0 { depth = 0 }
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Command {
+    Forward(i32),
+    Down(i32),
+    Up(i32)
+}
+
+impl Command {
+    // "forward 5", "down 3" or "up 1", with a non-negative amount
+    fn parse(encoded: &str) -> Result<Command, String> {
+        let tokens: Vec<&str> = encoded.split_whitespace().collect();
+        let (name, amount) = match tokens.as_slice() {
+            [name, amount] => (*name, *amount),
+            _ => return Err(format!("{:?} is not of the form <command> <amount>", encoded))
+        };
+        let amount = parse_amount(amount)?;
+        return match name {
+            "forward" => Ok(Command::Forward(amount)),
+            "down" => Ok(Command::Down(amount)),
+            "up" => Ok(Command::Up(amount)),
+            _ => Err(format!("Unknown command {:?}", name))
         }
     }
-    return (horizontal_position, depth)
 }
 
-fn calc_final_pos_and_depth_with_aim(movements: &Vec<String>) -> (i32, i32) {
-    let mut horizontal_position: i32 = 0;
-    let mut depth: i32 = 0;
-    let mut aim: i32 = 0;
-    for movement in movements {
-        let (command, coordinate) = parse_into_movement(movement);
-        if command.eq("forward") {
-            horizontal_position += coordinate;
-            depth += aim * coordinate
+fn parse_amount(encoded: &str) -> Result<i32, String> {
+    return match encoded.parse::<i32>() {
+        Ok(amount) if amount >= 0 => Ok(amount),
+        _ => Err(format!("Amount {:?} is not a non-negative integer", encoded))
+    }
+}
+
+// Blank lines are skipped, errors name the line they were found on
+fn parse_course(lines: &Vec<String>) -> Result<Vec<Command>, String> {
+    return lines.iter().enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(line_id, line)| Command::parse(line).map_err(|e| format!("Line {}: {}", line_id + 1, e)))
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+struct SubmarineState {
+    horizontal_position: i64,
+    depth: i64,
+    aim: i64
+}
+
+// None when the next state does not fit in the state fields
+trait NavigationModel {
+    fn apply(&self, state: &SubmarineState, command: &Command) -> Option<SubmarineState>;
+}
+
+// Part 1: down and up change the depth directly, which never goes above the surface
+struct SimpleNavigation;
+
+impl NavigationModel for SimpleNavigation {
+    fn apply(&self, state: &SubmarineState, command: &Command) -> Option<SubmarineState> {
+        let mut next = *state;
+        match *command {
+            Command::Forward(amount) => next.horizontal_position = next.horizontal_position.checked_add(amount.into())?,
+            Command::Down(amount) => next.depth = next.depth.checked_add(amount.into())?,
+            Command::Up(amount) => next.depth = (next.depth - i64::from(amount)).max(0)
+        }
+        return Some(next)
+    }
+}
+
+// Part 2: down and up change the aim, moving forward dives along it
+struct AimNavigation;
+
+impl NavigationModel for AimNavigation {
+    fn apply(&self, state: &SubmarineState, command: &Command) -> Option<SubmarineState> {
+        let mut next = *state;
+        match *command {
+            Command::Forward(amount) => {
+                next.horizontal_position = next.horizontal_position.checked_add(amount.into())?;
+                next.depth = next.depth.checked_add(next.aim.checked_mul(amount.into())?)?
+            },
+            Command::Down(amount) => next.aim = next.aim.checked_add(amount.into())?,
+            Command::Up(amount) => next.aim = next.aim.checked_sub(amount.into())?
+        }
+        return Some(next)
+    }
+}
+
+struct Submarine<'a> {
+    model: &'a dyn NavigationModel,
+    state: SubmarineState
+}
+
+impl<'a> Submarine<'a> {
+    fn new(model: &'a dyn NavigationModel) -> Submarine<'a> {
+        return Submarine { model, state: SubmarineState::default() }
+    }
+
+    fn execute(&mut self, command: &Command) -> Result<SubmarineState, String> {
+        self.state = self.model.apply(&self.state, command)
+            .ok_or_else(|| format!("{:?} overflows the submarine state", command))?;
+        return Ok(self.state)
+    }
+
+    // The starting state followed by the state after every command, errors name
+    // the command, counted from 1, which could not be executed
+    fn replay(&mut self, course: &[Command]) -> Result<Vec<SubmarineState>, String> {
+        let mut history = Vec::with_capacity(course.len() + 1);
+        history.push(self.state);
+        for (command_id, command) in course.iter().enumerate() {
+            history.push(self.execute(command).map_err(|e| format!("Command {}: {}", command_id + 1, e))?)
+        }
+        return Ok(history)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Side {
+    Left,
+    Right
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Instruction {
+    Basic(Command),
+    Turn(Side),
+    Strafe(Side, i32)
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Statement {
+    // The instruction with the script line it was written on
+    Do(Instruction, usize),
+    Repeat(u32, Vec<Statement>)
+}
+
+// Course scripts extend the day 2 commands, so plain day 2 inputs are valid scripts:
+//   forward N, down N, up N   as in day 2
+//   turn left, turn right     a quarter turn, the heading starts east
+//   strafe left N             sideways without turning, also strafe right N
+//   repeat N { ... }          the block N times
+//   macro name { ... }        defines a block, later run by writing its name
+// Everything after a # is a comment, line breaks are not significant.
+struct ScriptParser {
+    tokens: Vec<(String, usize)>,
+    position: usize,
+    macros: HashMap<String, Vec<Statement>>
+}
+
+impl ScriptParser {
+    fn new(lines: &Vec<String>) -> ScriptParser {
+        let tokens = lines.iter().enumerate().flat_map(|(line_id, line)| {
+            let code = line.split('#').next().unwrap().replace('{', " { ").replace('}', " } ");
+            code.split_whitespace().map(|t| (t.to_string(), line_id + 1)).collect::<Vec<(String, usize)>>()
+        }).collect();
+        return ScriptParser { tokens, position: 0, macros: HashMap::new() }
+    }
+
+    fn parse(lines: &Vec<String>) -> Result<Vec<Statement>, String> {
+        let mut parser = ScriptParser::new(lines);
+        return parser._block(None)
+    }
+
+    fn _next(&mut self, expected: &str) -> Result<(String, usize), String> {
+        let last_line = self.tokens.last().map(|(_, line)| *line).unwrap_or(1);
+        let token = self.tokens.get(self.position).cloned()
+            .ok_or_else(|| format!("Line {}: expected {}, found the end of the script", last_line, expected))?;
+        self.position += 1;
+        return Ok(token)
+    }
+
+    fn _amount(&mut self) -> Result<i32, String> {
+        let (token, line) = self._next("an amount")?;
+        return parse_amount(&token).map_err(|e| format!("Line {}: {}", line, e))
+    }
+
+    fn _side(&mut self) -> Result<Side, String> {
+        return match self._next("left or right")? {
+            (ref token, _) if token == "left" => Ok(Side::Left),
+            (ref token, _) if token == "right" => Ok(Side::Right),
+            (token, line) => Err(format!("Line {}: expected left or right, found {:?}", line, token))
         }
-        if command.eq("down") {
-            aim += coordinate
+    }
+
+    fn _opening_brace(&mut self) -> Result<usize, String> {
+        return match self._next("{")? {
+            (ref token, line) if token == "{" => Ok(line),
+            (token, line) => Err(format!("Line {}: expected {{, found {:?}", line, token))
         }
-        if command.eq("up") {
-            aim -= coordinate
+    }
+
+    // Statements up to the closing brace of a block opened on `opened_at`, or up
+    // to the end of the script at the top level
+    fn _block(&mut self, opened_at: Option<usize>) -> Result<Vec<Statement>, String> {
+        let mut statements = Vec::new();
+        loop {
+            let (token, line) = match (self.tokens.get(self.position).cloned(), opened_at) {
+                (Some(token), _) => token,
+                (None, None) => return Ok(statements),
+                (None, Some(opened_at)) => return Err(format!("Line {}: block is never closed", opened_at))
+            };
+            self.position += 1;
+            match token.as_str() {
+                "}" if opened_at.is_some() => return Ok(statements),
+                "}" => return Err(format!("Line {}: unexpected }}", line)),
+                "forward" => statements.push(Statement::Do(Instruction::Basic(Command::Forward(self._amount()?)), line)),
+                "down" => statements.push(Statement::Do(Instruction::Basic(Command::Down(self._amount()?)), line)),
+                "up" => statements.push(Statement::Do(Instruction::Basic(Command::Up(self._amount()?)), line)),
+                "turn" => statements.push(Statement::Do(Instruction::Turn(self._side()?), line)),
+                "strafe" => {
+                    let side = self._side()?;
+                    statements.push(Statement::Do(Instruction::Strafe(side, self._amount()?), line))
+                },
+                "repeat" => {
+                    let times = self._amount()? as u32;
+                    let opened_at = self._opening_brace()?;
+                    statements.push(Statement::Repeat(times, self._block(Some(opened_at))?))
+                },
+                "macro" => {
+                    let (name, name_line) = self._next("a macro name")?;
+                    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') || ScriptParser::_is_keyword(&name) {
+                        return Err(format!("Line {}: {:?} cannot be used as a macro name", name_line, name))
+                    }
+                    if self.macros.contains_key(&name) {
+                        return Err(format!("Line {}: macro {:?} is already defined", name_line, name))
+                    }
+                    let opened_at = self._opening_brace()?;
+                    let body = self._block(Some(opened_at))?;
+                    self.macros.insert(name, body);
+                },
+                name => match self.macros.get(name) {
+                    Some(body) => statements.extend(body.iter().cloned()),
+                    None => return Err(format!("Line {}: unknown command {:?}", line, name))
+                }
+            }
         }
     }
-    return (horizontal_position, depth)
+
+    fn _is_keyword(name: &str) -> bool {
+        return ["forward", "down", "up", "turn", "strafe", "repeat", "macro", "left", "right"].contains(&name)
+    }
+}
+
+// Position x grows eastwards, y southwards and z is the depth
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Pose {
+    position: Point3,
+    heading: Direction,
+    aim: i64
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct CourseReport {
+    pose: Pose,
+    distance: u64,
+    max_depth: i64
+}
+
+// Forward, down and up go through the navigation model, so the depth and aim follow
+// day 2 rules while forward moves along the heading. Distance adds up the horizontal
+// and vertical movement of every instruction. Errors name the script line of the
+// instruction after which the position, aim or distance no longer fits.
+struct ScriptInterpreter<'a> {
+    model: &'a dyn NavigationModel,
+    report: CourseReport
+}
+
+impl<'a> ScriptInterpreter<'a> {
+    fn new(model: &'a dyn NavigationModel) -> ScriptInterpreter<'a> {
+        let pose = Pose { position: Point3::new(0, 0, 0), heading: Direction::East, aim: 0 };
+        return ScriptInterpreter { model, report: CourseReport { pose, distance: 0, max_depth: 0 } }
+    }
+
+    fn run(&mut self, statements: &[Statement]) -> Result<CourseReport, String> {
+        for statement in statements {
+            match statement {
+                Statement::Do(instruction, line) => self.step(instruction)
+                    .ok_or_else(|| format!("Line {}: the submarine state overflows", line))?,
+                Statement::Repeat(times, body) => for _ in 0..*times { self.run(body)?; }
+            }
+        }
+        return Ok(self.report)
+    }
+
+    fn step(&mut self, instruction: &Instruction) -> Option<()> {
+        let pose = &mut self.report.pose;
+        let (forward, sideways) = match *instruction {
+            Instruction::Basic(command) => {
+                let before = SubmarineState { horizontal_position: 0, depth: pose.position.z, aim: pose.aim };
+                let after = self.model.apply(&before, &command)?;
+                pose.aim = after.aim;
+                let dive = (after.depth - pose.position.z).unsigned_abs();
+                self.report.distance = self.report.distance.checked_add(dive)?;
+                pose.position.z = after.depth;
+                (after.horizontal_position, 0)
+            },
+            Instruction::Turn(Side::Left) => { pose.heading = pose.heading.turn_left(); (0, 0) },
+            Instruction::Turn(Side::Right) => { pose.heading = pose.heading.turn_right(); (0, 0) },
+            Instruction::Strafe(Side::Left, amount) => (0, -i64::from(amount)),
+            Instruction::Strafe(Side::Right, amount) => (0, i64::from(amount))
+        };
+        let (ahead, right) = (pose.heading.delta(), pose.heading.turn_right().delta());
+        let delta = Point3::new(
+            i64::from(ahead.x) * forward + i64::from(right.x) * sideways,
+            i64::from(ahead.y) * forward + i64::from(right.y) * sideways,
+            0
+        );
+        pose.position = pose.position.checked_add(delta)?;
+        self.report.distance = self.report.distance.checked_add(forward.unsigned_abs() + sideways.unsigned_abs())?;
+        self.report.max_depth = self.report.max_depth.max(pose.position.z);
+        return Some(())
+    }
+}
+
+fn run_script(lines: &Vec<String>, model: &dyn NavigationModel) -> Result<CourseReport, String> {
+    let statements = ScriptParser::parse(lines)?;
+    return ScriptInterpreter::new(model).run(&statements)
+}
+
+fn navigation_model(name: &str) -> Result<&'static dyn NavigationModel, String> {
+    return match name {
+        "simple" => Ok(&SimpleNavigation),
+        "aim" => Ok(&AimNavigation),
+        _ => Err(format!("Unknown navigation model {:?}, expected simple or aim", name))
+    }
+}
+
+// Usage: course <script-file> [simple|aim], runs a course script with the aim model by default
+pub fn course_command(args: &[String]) {
+    let filename = match args.first() {
+        Some(filename) => filename,
+        None => panic!("Usage: course <script-file> [simple|aim]")
+    };
+    let model = navigation_model(args.get(1).map(|m| m.as_str()).unwrap_or("aim"))
+        .unwrap_or_else(|e| panic!("{}", e));
+    let report = run_script(&read_lines_as_str_vector(filename), model)
+        .unwrap_or_else(|e| panic!("Invalid course in {}: {}", filename, e));
+    let position = report.pose.position;
+    println!("Final position: x={} y={} depth={}", position.x, position.y, position.z);
+    println!("Heading: {:?}", report.pose.heading);
+    println!("Distance travelled: {}", report.distance);
+    println!("Maximum depth: {}", report.max_depth);
+}
+
+// Everything derived from replaying a course. The trajectory starts with the
+// initial state, so step i is the state after the i-th command.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct CourseAnalysis {
+    trajectory: Vec<SubmarineState>,
+    max_depth: i64,
+    max_depth_step: usize,
+    distance: u64
+}
+
+impl CourseAnalysis {
+    fn new(trajectory: Vec<SubmarineState>) -> Result<CourseAnalysis, String> {
+        // The first step at which the deepest point is reached
+        let (max_depth_step, max_depth) = trajectory.iter().enumerate()
+            .fold((0, trajectory[0].depth), |(best_step, best), (step, state)| {
+                if state.depth > best { (step, state.depth) } else { (best_step, best) }
+            });
+        let distance = trajectory.windows(2).try_fold(0u64, |distance, w| {
+            distance.checked_add((w[1].horizontal_position - w[0].horizontal_position).unsigned_abs())?
+                .checked_add((w[1].depth - w[0].depth).unsigned_abs())
+        }).ok_or_else(|| "The distance travelled overflows".to_string())?;
+        return Ok(CourseAnalysis { trajectory, max_depth, max_depth_step, distance })
+    }
+
+    fn final_state(&self) -> SubmarineState {
+        return *self.trajectory.last().unwrap()
+    }
+
+    fn to_csv(&self) -> Vec<String> {
+        let mut lines = vec!["step,horizontal_position,depth,aim".to_string()];
+        lines.extend(self.trajectory.iter().enumerate().map(|(step, state)| {
+            format!("{},{},{},{}", step, state.horizontal_position, state.depth, state.aim)
+        }));
+        return lines
+    }
+
+    fn write_csv(&self, file_path: &str) -> Result<(), String> {
+        let file = File::create(file_path).map_err(|e| format!("Cannot create {}: {}", file_path, e))?;
+        let mut out = BufWriter::new(file);
+        for line in self.to_csv() {
+            writeln!(out, "{}", line).map_err(|e| format!("Cannot write {}: {}", file_path, e))?;
+        }
+        return out.flush().map_err(|e| format!("Cannot write {}: {}", file_path, e))
+    }
+
+    fn _bounds(&self) -> ((i64, i64), (i64, i64)) {
+        let horizontal = self.trajectory.iter().map(|s| s.horizontal_position);
+        let depths = self.trajectory.iter().map(|s| s.depth);
+        return (
+            (horizontal.clone().min().unwrap(), horizontal.max().unwrap()),
+            (depths.clone().min().unwrap(), depths.max().unwrap())
+        )
+    }
+
+    // Maps a state to its position on a `width` x `height` canvas, depth grows
+    // downwards. The bounds are computed once, a zero size maps everything to 0.
+    fn _scale(&self, width: usize, height: usize) -> impl Fn(&SubmarineState) -> (usize, usize) {
+        let ((min_x, max_x), (min_depth, max_depth)) = self._bounds();
+        let scale = |value: i64, min: i64, max: i64, size: usize| {
+            ((value as i128 - min as i128) * (size.max(1) - 1) as i128 / (max as i128 - min as i128).max(1)) as usize
+        };
+        return move |state: &SubmarineState| (
+            scale(state.horizontal_position, min_x, max_x, width),
+            scale(state.depth, min_depth, max_depth, height)
+        )
+    }
+
+    // Depth against horizontal position, '*' marks visited states and '#' the deepest one
+    fn plot_ascii(&self, width: usize, height: usize) -> Vec<String> {
+        let mut canvas = vec![vec!['.'; width]; height];
+        if width > 0 && height > 0 {
+            let scale = self._scale(width, height);
+            for state in &self.trajectory {
+                let (x, y) = scale(state);
+                canvas[y][x] = '*';
+            }
+            let (x, y) = scale(&self.trajectory[self.max_depth_step]);
+            canvas[y][x] = '#';
+        }
+        return canvas.iter().map(|row| row.iter().collect()).collect()
+    }
+
+    fn plot_svg(&self, width: usize, height: usize) -> String {
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n",
+            w = width, h = height
+        );
+        let scale = self._scale(width, height);
+        let points = self.trajectory.iter()
+            .map(|state| {
+                let (x, y) = scale(state);
+                format!("{},{}", x, y)
+            })
+            .collect::<Vec<String>>().join(" ");
+        svg.push_str(&format!("<polyline points=\"{}\" fill=\"none\" stroke=\"#0000ff\" stroke-width=\"2\"/>\n", points));
+        let (x, y) = scale(&self.trajectory[self.max_depth_step]);
+        svg.push_str(&format!("<circle cx=\"{}\" cy=\"{}\" r=\"4\" fill=\"#ff0000\"/>\n", x, y));
+        svg.push_str("</svg>\n");
+        return svg
+    }
+}
+
+fn analyse_course(movements: &Vec<String>, model: &dyn NavigationModel) -> Result<CourseAnalysis, String> {
+    let course = parse_course(movements)?;
+    return CourseAnalysis::new(Submarine::new(model).replay(&course)?)
+}
+
+// Usage: course-trajectory <input-file> [simple|aim] [csv-file] [svg-file], prints a
+// summary and a plot of the course, optionally writing the trajectory as CSV and SVG
+pub fn course_trajectory_command(args: &[String]) {
+    let filename = match args.first() {
+        Some(filename) => filename,
+        None => panic!("Usage: course-trajectory <input-file> [simple|aim] [csv-file] [svg-file]")
+    };
+    let model = navigation_model(args.get(1).map(|m| m.as_str()).unwrap_or("aim"))
+        .unwrap_or_else(|e| panic!("{}", e));
+    let analysis = analyse_course(&read_lines_as_str_vector(filename), model)
+        .unwrap_or_else(|e| panic!("Invalid course in {}: {}", filename, e));
+    let last = analysis.final_state();
+    println!("Final position: {} depth: {} aim: {}", last.horizontal_position, last.depth, last.aim);
+    println!("Maximum depth: {} after {} commands", analysis.max_depth, analysis.max_depth_step);
+    println!("Distance travelled: {}", analysis.distance);
+    for line in analysis.plot_ascii(60, 20) {
+        println!("{}", line);
+    }
+    if let Some(csv_file) = args.get(2) {
+        analysis.write_csv(csv_file).unwrap_or_else(|e| panic!("{}", e));
+        println!("Wrote {} states to {}", analysis.trajectory.len(), csv_file);
+    }
+    if let Some(svg_file) = args.get(3) {
+        std::fs::write(svg_file, analysis.plot_svg(600, 300))
+            .unwrap_or_else(|e| panic!("Cannot write {}: {}", svg_file, e));
+        println!("Wrote plot to {}", svg_file);
+    }
+}
+
+fn navigate(movements: &Vec<String>, model: &dyn NavigationModel) -> SubmarineState {
+    let course = parse_course(movements).unwrap_or_else(|e| panic!("Invalid course: {}", e));
+    let mut submarine = Submarine::new(model);
+    let history = submarine.replay(&course).unwrap_or_else(|e| panic!("Invalid course: {}", e));
+    return *history.last().unwrap()
+}
+
+fn calc_final_pos_and_depth(movements: &Vec<String>) -> (i64, i64) {
+    let state = navigate(movements, &SimpleNavigation);
+    return (state.horizontal_position, state.depth)
+}
+
+fn calc_final_pos_and_depth_with_aim(movements: &Vec<String>) -> (i64, i64) {
+    let state = navigate(movements, &AimNavigation);
+    return (state.horizontal_position, state.depth)
 }
 
 pub fn day_2() {
@@ -44,13 +507,6 @@ pub fn day_2() {
     println!("Day 2 Part 2 result: {res}", res=result2.0 * result2.1);
 }
 
-fn parse_into_movement(movement: &String) -> (&str, i32) {
-    let mut split = movement.split_whitespace();
-    let command = split.next().unwrap();
-    let coordinate = split.next().unwrap().parse::<i32>().unwrap();
-    return (command, coordinate);
-}
-
 #[cfg(test)]
 mod tests {
     use crate::exs::utils::strs_to_strings;
@@ -83,4 +539,170 @@ mod tests {
 
         assert_eq!(calc_final_pos_and_depth_with_aim(&inputs), (15, 60));
     }
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(Command::parse("forward 5"), Ok(Command::Forward(5)));
+        assert_eq!(Command::parse("  up   0 "), Ok(Command::Up(0)));
+        assert_eq!(Command::parse("backward 2"), Err("Unknown command \"backward\"".to_string()));
+        assert_eq!(Command::parse("down -3"), Err("Amount \"-3\" is not a non-negative integer".to_string()));
+        assert_eq!(Command::parse("down x"), Err("Amount \"x\" is not a non-negative integer".to_string()));
+        assert_eq!(Command::parse("down"), Err("\"down\" is not of the form <command> <amount>".to_string()));
+        assert!(Command::parse("down 1 2").is_err());
+
+        assert_eq!(
+            parse_course(&strs_to_strings(&vec!["forward 1", "", "down 2"])),
+            Ok(vec![Command::Forward(1), Command::Down(2)])
+        );
+        assert_eq!(
+            parse_course(&strs_to_strings(&vec!["forward 1", "sideways 2"])),
+            Err("Line 2: Unknown command \"sideways\"".to_string())
+        );
+    }
+
+    #[test]
+    fn test_replay() {
+        let course = parse_course(&strs_to_strings(&vec!["forward 5", "down 5", "forward 8", "up 3"])).unwrap();
+        let state = |horizontal_position, depth, aim| SubmarineState { horizontal_position, depth, aim };
+
+        assert_eq!(Submarine::new(&AimNavigation).replay(&course), Ok(vec![
+            state(0, 0, 0),
+            state(5, 0, 0),
+            state(5, 0, 5),
+            state(13, 40, 5),
+            state(13, 40, 2)
+        ]));
+        let mut submarine = Submarine::new(&SimpleNavigation);
+        assert_eq!(submarine.replay(&course).unwrap().last(), Some(&state(13, 2, 0)));
+        assert_eq!(submarine.execute(&Command::Up(10)), Ok(state(13, 0, 0)));
+
+        let mut deep = Submarine { model: &AimNavigation, state: state(0, 0, i64::MAX) };
+        assert_eq!(deep.replay(&[Command::Forward(0), Command::Forward(2)]),
+            Err("Command 2: Forward(2) overflows the submarine state".to_string()));
+    }
+
+    #[test]
+    fn test_parse_script() {
+        let script = strs_to_strings(&vec![
+            "macro zigzag {  # comment",
+            "  forward 2 turn right",
+            "  forward 1 turn left",
+            "}",
+            "repeat 2 { zigzag }",
+            "strafe left 3"
+        ]);
+        let zigzag = vec![
+            Statement::Do(Instruction::Basic(Command::Forward(2)), 2),
+            Statement::Do(Instruction::Turn(Side::Right), 2),
+            Statement::Do(Instruction::Basic(Command::Forward(1)), 3),
+            Statement::Do(Instruction::Turn(Side::Left), 3)
+        ];
+        assert_eq!(ScriptParser::parse(&script), Ok(vec![
+            Statement::Repeat(2, zigzag),
+            Statement::Do(Instruction::Strafe(Side::Left, 3), 6)
+        ]));
+    }
+
+    #[test]
+    fn test_script_errors() {
+        let error = |lines: Vec<&str>| ScriptParser::parse(&strs_to_strings(&lines)).unwrap_err();
+        assert_eq!(error(vec!["forward 1", "dive 3"]), "Line 2: unknown command \"dive\"");
+        assert_eq!(error(vec!["forward", "", "up 2"]), "Line 3: Amount \"up\" is not a non-negative integer");
+        assert_eq!(error(vec!["down 1", "up"]), "Line 2: expected an amount, found the end of the script");
+        assert_eq!(error(vec!["turn around"]), "Line 1: expected left or right, found \"around\"");
+        assert_eq!(error(vec!["repeat 2 {", "forward 1"]), "Line 1: block is never closed");
+        assert_eq!(error(vec!["repeat 2 forward 1"]), "Line 1: expected {, found \"forward\"");
+        assert_eq!(error(vec!["forward 1 }"]), "Line 1: unexpected }");
+        assert_eq!(error(vec!["macro m { up 1 }", "macro m { up 2 }"]), "Line 2: macro \"m\" is already defined");
+        assert_eq!(error(vec!["macro up { up 1 }"]), "Line 1: \"up\" cannot be used as a macro name");
+        assert_eq!(error(vec!["macro m { m }"]), "Line 1: unknown command \"m\"");
+    }
+
+    #[test]
+    fn test_run_script() {
+        let script = strs_to_strings(&vec![
+            "down 2",
+            "repeat 4 { forward 3 turn right }",
+            "strafe right 2",
+            "turn left",
+            "forward 1",
+            "up 5"
+        ]);
+        let report = run_script(&script, &SimpleNavigation).unwrap();
+        assert_eq!(report.pose.position, Point3::new(0, 1, 0));
+        assert_eq!(report.pose.heading, Direction::North);
+        assert_eq!(report.distance, 2 + 12 + 2 + 1 + 2);
+        assert_eq!(report.max_depth, 2);
+
+        let aim = run_script(&strs_to_strings(&vec!["down 2", "forward 3", "turn left", "forward 1"]), &AimNavigation)
+            .unwrap();
+        assert_eq!(aim.pose, Pose { position: Point3::new(3, -1, 8), heading: Direction::North, aim: 2 });
+        assert_eq!(aim.distance, 3 + 6 + 1 + 2);
+        assert_eq!(aim.max_depth, 8);
+    }
+
+    #[test]
+    fn test_run_script_overflow() {
+        let deep = run_script(&strs_to_strings(&vec!["repeat 100 { down 1000 forward 1000 }"]), &AimNavigation)
+            .unwrap();
+        assert_eq!(deep.pose.position, Point3::new(100_000, 0, 5_050_000_000));
+        assert_eq!(deep.max_depth, 5_050_000_000);
+
+        let script = strs_to_strings(&vec![
+            "macro dive { down 2147483647 }",
+            "repeat 3 { dive }",
+            "forward 1",
+            "forward 2147483647"
+        ]);
+        assert_eq!(run_script(&script, &AimNavigation).unwrap_err(), "Line 4: the submarine state overflows");
+        assert!(run_script(&script, &SimpleNavigation).is_ok());
+    }
+
+    #[test]
+    fn test_day_2_inputs_are_scripts() {
+        let movements = read_lines_as_str_vector("data/day-2-dive/data-part-1.txt");
+        let simple = run_script(&movements, &SimpleNavigation).unwrap().pose.position;
+        assert_eq!((simple.x, simple.z), calc_final_pos_and_depth(&movements));
+        let aim = run_script(&movements, &AimNavigation).unwrap().pose.position;
+        assert_eq!((aim.x, aim.z), calc_final_pos_and_depth_with_aim(&movements));
+        assert_eq!(aim.y, 0);
+    }
+
+    #[test]
+    fn test_analyse_course() {
+        let movements = strs_to_strings(&vec!["forward 5", "down 5", "forward 8", "up 3", "down 8", "forward 2"]);
+        let analysis = analyse_course(&movements, &AimNavigation).unwrap();
+        assert_eq!(analysis.trajectory.len(), 7);
+        assert_eq!(analysis.final_state(), SubmarineState { horizontal_position: 15, depth: 60, aim: 10 });
+        assert_eq!((analysis.max_depth, analysis.max_depth_step), (60, 6));
+        assert_eq!(analysis.distance, 15 + 60);
+        assert_eq!(analysis.to_csv()[..3], strs_to_strings(&vec![
+            "step,horizontal_position,depth,aim", "0,0,0,0", "1,5,0,0"
+        ])[..]);
+
+        let simple = analyse_course(&movements, &SimpleNavigation).unwrap();
+        assert_eq!((simple.max_depth, simple.max_depth_step), (10, 5));
+        assert_eq!(simple.distance, 15 + 5 + 3 + 8);
+        assert!(analyse_course(&strs_to_strings(&vec!["forward 1", "back 2"]), &SimpleNavigation).is_err());
+    }
+
+    #[test]
+    fn test_plot_course() {
+        let movements = strs_to_strings(&vec!["forward 5", "down 5", "forward 8", "up 3", "down 8", "forward 2"]);
+        let analysis = analyse_course(&movements, &SimpleNavigation).unwrap();
+        assert_eq!(analysis.plot_ascii(16, 3), strs_to_strings(&vec![
+            "*....*.......*..",
+            ".....*.......*..",
+            ".............#.*"
+        ]));
+        let svg = analysis.plot_svg(16, 3);
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("points=\"0,0 5,0 5,1 13,1 13,0 13,2 15,2\""));
+        assert!(svg.contains("<circle cx=\"13\" cy=\"2\""));
+
+        assert!(analysis.plot_ascii(0, 2).iter().all(|row| row.is_empty()));
+        assert!(analysis.plot_ascii(4, 0).is_empty());
+        assert_eq!(analysis.plot_ascii(1, 1), strs_to_strings(&vec!["#"]));
+        assert!(analysis.plot_svg(0, 0).contains("points=\"0,0 0,0 0,0 0,0 0,0 0,0 0,0\""));
+    }
 }
@@ -1,64 +1,35 @@
-use std::collections::HashMap;
-use crate::exs::utils::{open_range_vec, read_lines_as_str_vector};
-
-#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug)]
-struct Point {
-    x: u32,
-    y: u32
-}
-
-impl Point {
-    fn from_string(input: String) -> Point {
-        let pair: Vec<u32> = input.split(",")
-            .map(|d| d.parse::<u32>().unwrap()).collect();
-        return Point { x: *pair.first().unwrap(), y: *pair.last().unwrap() }
-    }
+use crate::exs::point::Point2;
+use crate::exs::sparse_grid::SparseGrid;
+use crate::exs::utils::read_lines_as_str_vector;
+
+fn parse_point(input: &str) -> Point2 {
+    let pair: Vec<i32> = input.split(",")
+        .map(|d| d.parse::<i32>().unwrap()).collect();
+    return Point2::new(*pair.first().unwrap(), *pair.last().unwrap())
 }
 
 #[derive(Clone, Debug)]
 struct LineOfVents {
-    start: Point,
-    end: Point,
-    line: Vec<Point>
+    start: Point2,
+    end: Point2,
+    line: Vec<Point2>
 }
 
 impl LineOfVents {
     fn from_string(input: &String) -> LineOfVents {
-        let start_and_end: Vec<Point> = input.split(" -> ")
-            .map(|p| Point::from_string(p.to_string())).collect();
+        let start_and_end: Vec<Point2> = input.split(" -> ").map(parse_point).collect();
 
-        let start = start_and_end.first().unwrap().clone();
-        let end = start_and_end.last().unwrap().clone();
+        let start = *start_and_end.first().unwrap();
+        let end = *start_and_end.last().unwrap();
         return LineOfVents { start, end, line: LineOfVents::build_line(start, end) }
     }
 
-    fn build_line(start: Point, end: Point) -> Vec<Point> {
-        if start.x == end.x { LineOfVents::_build_vertical_line(start, end) }
-        else if start.y == end.y { LineOfVents::_build_horizontal_line(start, end) }
-        else { LineOfVents::_build_diagonal_line(start, end) }
-    }
-
-    fn _build_horizontal_line(start: Point, end: Point) -> Vec<Point> {
-        open_range_vec(start.x, end.x).iter()
-            .map(|x| Point {x: *x, y: end.y}).collect()
-    }
-
-    fn _build_vertical_line(start: Point, end: Point) -> Vec<Point> {
-        open_range_vec(start.y, end.y).iter()
-            .map(|y| Point {x: end.x, y: *y}).collect()
-    }
-
-    fn _build_diagonal_line(start: Point, end: Point) -> Vec<Point> {
-        // TODO: simplify
-        let mut line: Vec<Point> = Vec::new();
-        let xes = open_range_vec(start.x, end.x);
-        let mut ycs = open_range_vec(start.y, end.y).into_iter();
-
-        for x in xes {
-            // unwrap() will panic if the line is not truly diagonal
-            line.push (Point { x, y: ycs.next().unwrap().clone() })
-        }
-        return line;
+    // Lines are horizontal, vertical or at exactly 45 degrees, so one step
+    // towards the end moves by at most 1 along each axis
+    fn build_line(start: Point2, end: Point2) -> Vec<Point2> {
+        let step = (end - start).signum();
+        let n_steps = start.chebyshev(&end) as i32;
+        return (0..=n_steps).map(|i| start + step * i).collect()
     }
 }
 
@@ -69,10 +40,11 @@ fn parse_input(nearby_lines_encoded: &Vec<String>) -> Vec<LineOfVents> {
 }
 
 fn count_points_where_lines_overlap(nearby_lines: &Vec<LineOfVents>) -> u32 {
-    let mut points_with_lines_count: HashMap<Point, u32> = HashMap::new();
+    let mut points_with_lines_count: SparseGrid<u32> = SparseGrid::new();
     for line in nearby_lines {
         for point in &line.line {
-            let existing = points_with_lines_count.entry(point.clone()).or_insert(0);
+            let existing = points_with_lines_count
+                .entry_or_insert(point.x, point.y, 0);
             *existing += 1
         }
     }
@@ -201,11 +173,23 @@ mod tests {
         assert_eq!(parse_and_count_points_where_lines_overlap(&inputs, false), 2);
     }
 
+    #[test]
+    fn test_build_line() {
+        assert_eq!(
+            LineOfVents::build_line(Point2::new(3, 1), Point2::new(1, 3)),
+            vec![Point2::new(3, 1), Point2::new(2, 2), Point2::new(1, 3)]
+        );
+        assert_eq!(
+            LineOfVents::build_line(Point2::new(2, 2), Point2::new(2, 2)),
+            vec![Point2::new(2, 2)]
+        );
+    }
+
     #[test]
     fn points_equality() {
-        let p = Point {x: 0 , y: 0};
+        let p = parse_point("0,0");
         assert_eq!(p, p);
-        assert_eq!(p, Point {x: 0 , y: 0});
-        assert_ne!(p, Point {x: 0 , y: 1});
+        assert_eq!(p, Point2 {x: 0 , y: 0});
+        assert_ne!(p, Point2 {x: 0 , y: 1});
     }
 }
@@ -1,162 +1,79 @@
 use std::collections::HashSet;
 use super::matrix::Matrix;
+use super::movement::MovementModel;
+use super::point::Point2;
 
 use super::utils::read_lines_as_str_vector;
 
 #[derive(Debug)]
 struct HeightMap {
-    points: Vec<Vec<Point>>,
-    n_rows: usize,
-    n_columns: usize
+    heights: Matrix<u32>
 }
 
 impl HeightMap {
     fn new(rows: &Vec<String>) -> HeightMap {
-        let m = Matrix::<u32>::from_lines(rows);
-        let n_rows = m.n_rows();
-        let n_columns = m.n_columns();
-        let points: Vec<Vec<Point>> = (0..n_rows).map(
-            |row_id| (0..n_columns).map(
-                |col_id| Point {
-                    x: col_id as u32,
-                    y: row_id as u32,
-                    height: m.get_point(col_id as i32, row_id as i32).unwrap(),
-                    is_low: None
-                }
-            ).collect()
-        ).collect();
-
-        return HeightMap { points, n_rows, n_columns }
+        return HeightMap { heights: Matrix::<u32>::from_lines(rows) }
     }
 
-    fn get_point(&self, x: i32, y: i32) -> Option<Point> {
-        if x < 0 || y < 0 { return None };
-        let (x_, y_) = (x as usize, y as usize);
-        if x_ >= self.n_columns || y_ >= self.n_rows { return None };
-        return self.points.get(y_).unwrap().get(x_).cloned()
+    fn height(&self, position: Point2) -> Option<u32> {
+        return self.heights.get(position)
     }
 
-    fn neighbour_points(&self, target: &Point) -> HashSet<Point> {
-        let x_ = target.x as i32;
-        let y_ = target.y as i32;
-        let indexes_to_find = [
-            (x_ + 1, y_),
-            (x_ - 1, y_),
-            (x_, y_ + 1),
-            (x_, y_ - 1)
-        ];
-
-        let mut neighbour_points: HashSet<Point> = HashSet::new();
-
-        for pair in indexes_to_find {
-            let maybe_point = HeightMap::get_point(self, pair.0, pair.1);
-            match maybe_point {
-                Some(p) => { neighbour_points.insert(p); },
-                None => {}
-            }
-        }
-
-        return neighbour_points
+    fn neighbours(&self, position: Point2) -> HashSet<Point2> {
+        return position.neighbours(false).into_iter()
+            .filter(|neighbour| self.heights.contains(*neighbour))
+            .collect()
     }
 
-    fn points_iter(&self) -> impl Iterator<Item=Point> + '_ {
-        let row_ids = 0..(self.n_rows);
-        let col_ids = 0..(self.n_columns);
-        return row_ids.flat_map(move |y| col_ids.clone().map(move |x| (x, y)))
-            .map(move |(x, y)| self.points.get(y).unwrap().get(x).unwrap())
-            .map(|p| p.clone())
+    fn positions(&self) -> impl Iterator<Item=Point2> + '_ {
+        let col_ids = 0..self.heights.n_columns();
+        return (0..self.heights.n_rows())
+            .flat_map(move |y| col_ids.clone().map(move |x| Point2::from((x, y))))
     }
 }
 
-
-#[derive(Clone, Eq, PartialEq, Debug, Hash)]
-struct Point {
-    x: u32,
-    y: u32,
-    height: u32,
-    is_low: Option<bool>
-}
-
-
-fn find_low_points(height_map: &HeightMap) -> Vec<Point> {
+fn find_low_points(height_map: &HeightMap) -> Vec<Point2> {
     let mut points = Vec::new();
-    for point in height_map.points_iter() {
-        let height = point.height;
-        let neighbour_points = height_map.neighbour_points(&point);
-        let neighbour_points_heights_min = neighbour_points.iter()
-            .map(|p| p.height).min().unwrap();
+    for position in height_map.positions() {
+        let height = height_map.height(position).unwrap();
+        let neighbour_heights_min = height_map.neighbours(position).iter()
+            .filter_map(|p| height_map.height(*p)).min().unwrap();
 
-        if height < neighbour_points_heights_min { points.push(point.clone()) }
+        if height < neighbour_heights_min { points.push(position) }
     }
     return points
 }
 
-fn compute_risk(low_points: &Vec<Point>) -> u32 {
-    return low_points.iter().map(|p| p.height + 1).sum()
+fn compute_risk(height_map: &HeightMap, low_points: &Vec<Point2>) -> u32 {
+    return low_points.iter().map(|p| height_map.height(*p).unwrap() + 1).sum()
 }
 
 fn compute_risk_level(input: &Vec<String>) -> u32 {
     let map = HeightMap::new(input);
     let low_points = find_low_points(&map);
-    return compute_risk(&low_points)
+    return compute_risk(&map, &low_points)
 }
 
-fn find_basin_via_low_point<'a>(
-    map: &HeightMap,
-    low_point: &Point,
-    basin_points_found_so_far: &'a mut HashSet<Point>,
-    recursion_depth: usize
-) -> &'a mut HashSet<Point> {
-
-    let recursion_depth_max = 10_000usize;
-    if recursion_depth > 10_000 { panic!("Recursion depth max {} is reached", recursion_depth_max) }
-
-    basin_points_found_so_far.insert(low_point.clone());
-
-    let neighbours = map.neighbour_points(low_point);
-    if neighbours.is_empty() { return basin_points_found_so_far }
-
-    for neighbour in neighbours {
-        if neighbour.height == 9 { continue }
-        if neighbour.height > low_point.height {
-            find_basin_via_low_point(
-                map,
-                &neighbour,
-                basin_points_found_so_far,
-                recursion_depth + 1
-            );
-        }
-    }
-
-    return basin_points_found_so_far
+fn find_basin_sizes(map: &HeightMap) -> Vec<usize> {
+    return map.heights.label_components(false, |height| *height != 9).sizes()
 }
 
-fn find_basins_via_low_points(map: &HeightMap, low_points: &Vec<Point>) -> Vec<HashSet<Point>> {
-    let mut basins = Vec::new();
-    for low_point in low_points {
-        let basin = &mut HashSet::<Point>::new();
-        find_basin_via_low_point(map, low_point, basin, 0);
-        basins.push(basin.clone())
-    }
-    return basins
+// Number of steps from every location to the closest low point
+fn distances_to_low_points(map: &HeightMap) -> Matrix<Option<u64>> {
+    let low_points: Vec<(i32, i32)> = find_low_points(map).into_iter().map(|p| p.into()).collect();
+    return map.heights.bfs_distance_map(&low_points, &MovementModel::orthogonal()).distances
 }
 
 fn find_basins(input: &Vec<String>) -> u32 {
     let map = HeightMap::new(input);
-    let low_points = find_low_points(&map);
-    let basins = find_basins_via_low_points(&map, &low_points);
-    return compute_basins_result(&basins)
+    let basin_sizes = find_basin_sizes(&map);
+    return compute_basins_result(&basin_sizes)
 }
 
-fn compute_basins_result(basins: &Vec<HashSet<Point>>) -> u32 {
-    let mut basins_to_sort = basins.clone();
-    basins_to_sort.sort_by_key(|b| - (b.len() as i32));
-    let mut v = 1u32;
-    for (i, b) in basins_to_sort.iter().enumerate() {
-        if i >= 3 { break }
-        v = v * b.len() as u32;
-    }
-    return v
+fn compute_basins_result(basin_sizes: &Vec<usize>) -> u32 {
+    let mut sizes_to_sort = basin_sizes.clone();
+    sizes_to_sort.sort_by_key(|size| - (*size as i64));
+    return sizes_to_sort.iter().take(3).map(|size| *size as u32).product()
 }
 
 pub fn day_9() {
@@ -168,6 +85,26 @@ pub fn day_9() {
     println!("Day 9 Part 2 result: {res}", res=result_basins);
 }
 
+// Usage: smoke-distances <input-file>, prints how many steps every location is from
+// the closest low point, one row of the height map per line
+pub fn smoke_distances_command(args: &[String]) {
+    let filename = match args.first() {
+        Some(filename) => filename,
+        None => panic!("Usage: smoke-distances <input-file>")
+    };
+    let distances = distances_to_low_points(&HeightMap::new(&read_lines_as_str_vector(filename)));
+    for y in 0..distances.n_rows() {
+        let row: Vec<String> = distances.row(y).iter()
+            .map(|d| d.map(|d| d.to_string()).unwrap_or_else(|| "-".to_string()))
+            .collect();
+        println!("{}", row.join(" "))
+    }
+    match distances.points_iter().flatten().max() {
+        Some(farthest) => println!("Farthest location: {} steps from a low point", farthest),
+        None => println!("There are no low points")
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::exs::utils::strs_to_strings;
@@ -176,7 +113,7 @@ mod tests {
     #[test]
     fn test_map() {
         let height_map = HeightMap::new(
-            &strs_to_strings(&&vec![
+            &strs_to_strings(&vec![
                 "012",
                 "345",
                 "678",
@@ -184,17 +121,17 @@ mod tests {
             ])
         );
 
-        assert_eq!(height_map.get_point(0, 0), Some(Point { x: 0, y: 0, height: 0, is_low: None }));
-        assert_eq!(height_map.get_point(0, 1), Some(Point { x: 0, y: 1, height: 3, is_low: None }));
-        assert_eq!(height_map.get_point(2, 3), Some(Point { x: 2, y: 3, height: 1, is_low: None }));
-        assert_eq!(height_map.get_point(3, 3), None);
-        assert_eq!(height_map.get_point(-1, 3), None);
+        assert_eq!(height_map.height(Point2::new(0, 0)), Some(0));
+        assert_eq!(height_map.height(Point2::new(0, 1)), Some(3));
+        assert_eq!(height_map.height(Point2::new(2, 3)), Some(1));
+        assert_eq!(height_map.height(Point2::new(3, 3)), None);
+        assert_eq!(height_map.height(Point2::new(-1, 3)), None);
 
-        assert_eq!(height_map.points_iter().map(|p| p.height).sum::<u32>(), 46);
+        assert_eq!(height_map.positions().filter_map(|p| height_map.height(p)).sum::<u32>(), 46);
     }
 
     #[test]
-    fn test_get_neighbour_points() {
+    fn test_get_neighbours() {
         let height_map = HeightMap::new(
             &strs_to_strings(&vec![
                 "012",
@@ -205,21 +142,13 @@ mod tests {
         );
 
         assert_eq!(
-            height_map.neighbour_points(&Point { x: 0, y: 0, height: 0, is_low: None }),
-            [
-                Point { x: 0, y: 1, height: 3, is_low: None },
-                Point { x: 1, y: 0, height: 1, is_low: None }
-            ].iter().cloned().collect()
+            height_map.neighbours(Point2::new(0, 0)),
+            [Point2::new(0, 1), Point2::new(1, 0)].iter().cloned().collect()
         );
 
         assert_eq!(
-            height_map.neighbour_points(&Point { x: 1, y: 1, height: 4, is_low: None }),
-            [
-                Point { x: 1, y: 0, height: 1, is_low: None },
-                Point { x: 0, y: 1, height: 3, is_low: None },
-                Point { x: 2, y: 1, height: 5, is_low: None },
-                Point { x: 1, y: 2, height: 7, is_low: None }
-            ].iter().cloned().collect()
+            height_map.neighbours(Point2::new(1, 1)),
+            [Point2::new(1, 0), Point2::new(0, 1), Point2::new(2, 1), Point2::new(1, 2)].iter().cloned().collect()
         );
     }
 
@@ -247,5 +176,28 @@ mod tests {
         ]);
 
         assert_eq!(find_basins(&input), 1134);
+
+        let mut basin_sizes = find_basin_sizes(&HeightMap::new(&input));
+        basin_sizes.sort();
+        assert_eq!(basin_sizes, vec![3, 9, 9, 14]);
+    }
+
+    #[test]
+    fn test_distances_to_low_points() {
+        let input = strs_to_strings(&vec![
+            "2199943210",
+            "3987894921",
+            "9856789892",
+            "8767896789",
+            "9899965678"
+        ]);
+        let distances = distances_to_low_points(&HeightMap::new(&input)).map(|d| d.unwrap());
+        assert_eq!(distances.to_lines().unwrap(), strs_to_strings(&vec![
+            "1012343210",
+            "2112343321",
+            "2101232332",
+            "3212321233",
+            "4323210123"
+        ]));
     }
 }
@@ -0,0 +1,240 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet, VecDeque};
+use super::matrix::Matrix;
+use super::movement::MovementModel;
+use super::point::Point2;
+
+// Cheapest cost of reaching every cell from the nearest source, None for cells
+// which cannot be reached. Following the predecessors from a cell walks back
+// along one cheapest path to that source.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DistanceMap {
+    pub distances: Matrix<Option<u64>>,
+    pub predecessors: Matrix<Option<(i32, i32)>>
+}
+
+impl DistanceMap {
+    fn new(n_rows: usize, n_columns: usize) -> DistanceMap {
+        return DistanceMap {
+            distances: Matrix::filled(n_rows, n_columns, None),
+            predecessors: Matrix::filled(n_rows, n_columns, None)
+        }
+    }
+
+    pub fn distance(&self, x: i32, y: i32) -> Option<u64> {
+        return self.distances.get_point(x, y).flatten()
+    }
+
+    // Cells from the nearest source to (x, y), both included
+    pub fn path_to(&self, x: i32, y: i32) -> Option<Vec<(i32, i32)>> {
+        self.distance(x, y)?;
+        let mut cells = vec![(x, y)];
+        while let Some(previous) = self.predecessors.get(*cells.last().unwrap()).unwrap() {
+            cells.push(previous)
+        }
+        cells.reverse();
+        return Some(cells)
+    }
+
+    pub fn max_distance(&self) -> Option<u64> {
+        return self.distances.points_iter().flatten().max()
+    }
+}
+
+// Cells and single moves between cells which a search may not use
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Blocked {
+    pub cells: HashSet<(i32, i32)>,
+    pub moves: HashSet<((i32, i32), (i32, i32))>
+}
+
+impl Blocked {
+    fn allows(&self, from: (i32, i32), to: (i32, i32)) -> bool {
+        return !self.cells.contains(&to) && !self.moves.contains(&(from, to))
+    }
+}
+
+impl<T: Clone + 'static> Matrix<T> {
+    pub fn distance_map(&self, source: (i32, i32), movement: &MovementModel<T>) -> DistanceMap {
+        return self.multi_source_distance_map(&[source], movement)
+    }
+
+    // Dijkstra started from all the sources at once
+    pub fn multi_source_distance_map(&self, sources: &[(i32, i32)], movement: &MovementModel<T>) -> DistanceMap {
+        return self._search(sources, movement, &Blocked::default(), None, |_| 0)
+    }
+
+    // Cheapest path from `from` to `to` avoiding the blocked cells and moves, with its
+    // cost. With `min_move_cost` set and orthogonal movement the Manhattan distance to
+    // `to` times that cost guides the search (A*), which is only correct when no move
+    // costs less than `min_move_cost`.
+    pub fn shortest_path(
+        &self,
+        from: (i32, i32),
+        to: (i32, i32),
+        movement: &MovementModel<T>,
+        blocked: &Blocked,
+        min_move_cost: Option<u64>
+    ) -> Option<(u64, Vec<(i32, i32)>)> {
+        if !self.contains(from) || !self.contains(to) { return None }
+        if blocked.cells.contains(&from) || blocked.cells.contains(&to) { return None }
+        let scale = min_move_cost.filter(|_| movement.is_orthogonal()).unwrap_or(0);
+        let target = Point2::from(to);
+        let heuristic = |cell: (i32, i32)| Point2::from(cell).manhattan(&target) as u64 * scale;
+        let map = self._search(&[from], movement, blocked, Some(to), heuristic);
+        return Some((map.distance(to.0, to.1)?, map.path_to(to.0, to.1)?))
+    }
+
+    // Dijkstra, or A* with a heuristic other than zero, stopping once `target` is
+    // reached. Cells which have not been settled by then may hold tentative distances.
+    fn _search<H>(
+        &self,
+        sources: &[(i32, i32)],
+        movement: &MovementModel<T>,
+        blocked: &Blocked,
+        target: Option<(i32, i32)>,
+        heuristic: H
+    ) -> DistanceMap where H: Fn((i32, i32)) -> u64 {
+        let mut map = DistanceMap::new(self.n_rows(), self.n_columns());
+        let mut queue = BinaryHeap::new();
+        for source in sources.iter().filter(|s| self.contains(**s) && !blocked.cells.contains(*s)) {
+            *map.distances.get_mut(*source).unwrap() = Some(0);
+            queue.push(Reverse((heuristic(*source), 0, *source)));
+        }
+
+        while let Some(Reverse((_, distance, (x, y)))) = queue.pop() {
+            if target == Some((x, y)) { break }
+            if map.distance(x, y).map(|d| distance > d).unwrap_or(false) { continue }
+            for (next, cost) in movement.successors(self, x, y) {
+                if !blocked.allows((x, y), next) { continue }
+                let next_distance = distance + cost;
+                if map.distances.get(next).unwrap().map(|d| next_distance < d).unwrap_or(true) {
+                    *map.distances.get_mut(next).unwrap() = Some(next_distance);
+                    *map.predecessors.get_mut(next).unwrap() = Some((x, y));
+                    queue.push(Reverse((next_distance + heuristic(next), next_distance, next)));
+                }
+            }
+        }
+        return map
+    }
+
+    // Every move counts as one step whatever it costs, cells which cannot be
+    // entered under the movement model are still avoided
+    pub fn bfs_distance_map(&self, sources: &[(i32, i32)], movement: &MovementModel<T>) -> DistanceMap {
+        let mut map = DistanceMap::new(self.n_rows(), self.n_columns());
+        let mut queue = VecDeque::new();
+        for source in sources.iter().filter(|(x, y)| self.index_exists(*x, *y)) {
+            if map.distance(source.0, source.1).is_some() { continue }
+            *map.distances.get_mut(*source).unwrap() = Some(0);
+            queue.push_back(*source);
+        }
+
+        while let Some((x, y)) = queue.pop_front() {
+            let distance = map.distance(x, y).unwrap();
+            for (next, _) in movement.successors(self, x, y) {
+                if map.distances.get(next).unwrap().is_some() { continue }
+                *map.distances.get_mut(next).unwrap() = Some(distance + 1);
+                *map.predecessors.get_mut(next).unwrap() = Some((x, y));
+                queue.push_back(next);
+            }
+        }
+        return map
+    }
+
+    // Entry (i, j) is the cheapest cost from points[i] to points[j]
+    pub fn pairwise_distances(&self, points: &[(i32, i32)], movement: &MovementModel<T>) -> Matrix<Option<u64>> {
+        let rows = points.iter().map(|from| {
+            let map = self.distance_map(*from, movement);
+            points.iter().map(|(x, y)| map.distance(*x, *y)).collect()
+        }).collect();
+        return Matrix::from_rows(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exs::utils::strs_to_strings;
+
+    fn grid() -> Matrix<u32> {
+        return Matrix::<u32>::from_lines(&strs_to_strings(&[
+            "1163",
+            "1381",
+            "2136"
+        ]))
+    }
+
+    #[test]
+    fn test_single_source() {
+        let map = grid().distance_map((0, 0), &MovementModel::orthogonal());
+        assert_eq!(map.distance(0, 0), Some(0));
+        assert_eq!(map.distance(3, 2), Some(13));
+        assert_eq!(map.distance(4, 0), None);
+        assert_eq!(map.path_to(3, 2).unwrap(), vec![(0, 0), (0, 1), (0, 2), (1, 2), (2, 2), (3, 2)]);
+        assert_eq!(map.path_to(0, 0).unwrap(), vec![(0, 0)]);
+        assert_eq!(map.max_distance(), Some(13));
+    }
+
+    #[test]
+    fn test_multi_source_and_walls() {
+        let movement = MovementModel::orthogonal().with_walls(|v: &u32| *v >= 6);
+        let map = grid().multi_source_distance_map(&[(0, 0), (3, 1)], &movement);
+        assert_eq!(map.distance(3, 1), Some(0));
+        assert_eq!(map.distance(3, 0), Some(3));
+        assert_eq!(map.distance(2, 2), Some(7));
+        assert_eq!(map.distance(2, 1), None);
+        assert_eq!(map.path_to(3, 0).unwrap(), vec![(3, 1), (3, 0)]);
+        assert_eq!(map.path_to(2, 1), None);
+        assert_eq!(map.distances.count_where(|d| d.is_none()), 3);
+    }
+
+    #[test]
+    fn test_shortest_path() {
+        let movement = MovementModel::orthogonal();
+        let expected = vec![(0, 0), (0, 1), (0, 2), (1, 2), (2, 2), (3, 2)];
+        for min_move_cost in [None, Some(1)].iter() {
+            let path = grid().shortest_path((0, 0), (3, 2), &movement, &Blocked::default(), *min_move_cost);
+            assert_eq!(path, Some((13, expected.clone())));
+        }
+
+        let mut blocked = Blocked::default();
+        blocked.cells.insert((0, 2));
+        blocked.moves.insert(((1, 0), (1, 1)));
+        let (cost, cells) = grid().shortest_path((0, 0), (3, 2), &movement, &blocked, Some(1)).unwrap();
+        assert_eq!(cost, 14);
+        assert_eq!(cells, vec![(0, 0), (0, 1), (1, 1), (1, 2), (2, 2), (3, 2)]);
+
+        assert_eq!(grid().shortest_path((0, 0), (0, 2), &movement, &blocked, Some(1)), None);
+        assert_eq!(grid().shortest_path((0, 0), (4, 2), &movement, &Blocked::default(), Some(1)), None);
+        assert_eq!(grid().shortest_path((2, 1), (2, 1), &movement, &Blocked::default(), Some(1)), Some((0, vec![(2, 1)])));
+
+        let free = Matrix::<u32>::from_lines(&strs_to_strings(&["09000", "09090", "00010"]));
+        for min_move_cost in [None, Some(0)].iter() {
+            let (cost, _) = free.shortest_path((0, 0), (4, 2), &movement, &Blocked::default(), *min_move_cost).unwrap();
+            assert_eq!(cost, 0);
+        }
+    }
+
+    #[test]
+    fn test_bfs() {
+        let map = grid().bfs_distance_map(&[(0, 0), (3, 2)], &MovementModel::orthogonal());
+        assert_eq!(
+            map.distances.map(|d| d.unwrap()),
+            Matrix::from_rows(vec![vec![0, 1, 2, 2], vec![1, 2, 2, 1], vec![2, 2, 1, 0]])
+        );
+        let walled = grid().bfs_distance_map(&[(0, 0)], &MovementModel::orthogonal().with_walls(|v: &u32| *v == 6));
+        assert_eq!(walled.distance(2, 0), None);
+        assert_eq!(walled.distance(3, 0), Some(5));
+        assert_eq!(walled.path_to(3, 0).unwrap().len(), 6);
+    }
+
+    #[test]
+    fn test_pairwise_distances() {
+        let distances = grid().pairwise_distances(&[(0, 0), (3, 2), (9, 9)], &MovementModel::orthogonal());
+        assert_eq!(distances.get((0, 0)).unwrap(), Some(0));
+        assert_eq!(distances.get((1, 0)).unwrap(), Some(13));
+        assert_eq!(distances.get((0, 1)).unwrap(), Some(8));
+        assert_eq!(distances.get((2, 0)).unwrap(), None);
+        assert_eq!(distances.get((2, 2)).unwrap(), None);
+    }
+}
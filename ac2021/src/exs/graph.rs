@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+pub type NodeId = usize;
+
+// Nodes are interned by name into small integer ids, so algorithms can index
+// plain vectors instead of hashing strings
+#[derive(Clone, Debug)]
+pub struct Graph<N, W> {
+    directed: bool,
+    names: Vec<String>,
+    ids: HashMap<String, NodeId>,
+    attributes: Vec<N>,
+    adjacency: Vec<Vec<(NodeId, W)>>
+}
+
+impl<N, W: Clone> Graph<N, W> {
+    pub fn directed() -> Graph<N, W> {
+        return Graph::_new(true)
+    }
+
+    pub fn undirected() -> Graph<N, W> {
+        return Graph::_new(false)
+    }
+
+    fn _new(directed: bool) -> Graph<N, W> {
+        return Graph {
+            directed,
+            names: Vec::new(),
+            ids: HashMap::new(),
+            attributes: Vec::new(),
+            adjacency: Vec::new()
+        }
+    }
+
+    pub fn is_directed(&self) -> bool {
+        return self.directed
+    }
+
+    pub fn intern<F>(&mut self, name: &str, make_attribute: F) -> NodeId where F: FnOnce() -> N {
+        if let Some(id) = self.ids.get(name) { return *id }
+        let id = self.names.len();
+        self.names.push(name.to_string());
+        self.ids.insert(name.to_string(), id);
+        self.attributes.push(make_attribute());
+        self.adjacency.push(Vec::new());
+        return id
+    }
+
+    pub fn node_id(&self, name: &str) -> Option<NodeId> {
+        return self.ids.get(name).cloned()
+    }
+
+    pub fn name(&self, id: NodeId) -> &str {
+        return &self.names[id]
+    }
+
+    pub fn attribute(&self, id: NodeId) -> &N {
+        return &self.attributes[id]
+    }
+
+    pub fn attribute_mut(&mut self, id: NodeId) -> &mut N {
+        return &mut self.attributes[id]
+    }
+
+    pub fn n_nodes(&self) -> usize {
+        return self.names.len()
+    }
+
+    pub fn node_ids(&self) -> Range<NodeId> {
+        return 0..self.names.len()
+    }
+
+    // Adding an edge which already exists replaces its weight
+    pub fn add_edge(&mut self, from: NodeId, to: NodeId, weight: W) {
+        self._add_arc(from, to, weight.clone());
+        if !self.directed && from != to { self._add_arc(to, from, weight) }
+    }
+
+    fn _add_arc(&mut self, from: NodeId, to: NodeId, weight: W) {
+        let arcs = &mut self.adjacency[from];
+        match arcs.iter_mut().find(|(target, _)| *target == to) {
+            Some(arc) => arc.1 = weight,
+            None => arcs.push((to, weight))
+        }
+    }
+
+    pub fn has_edge(&self, from: NodeId, to: NodeId) -> bool {
+        return self.edge_weight(from, to).is_some()
+    }
+
+    pub fn edge_weight(&self, from: NodeId, to: NodeId) -> Option<&W> {
+        return self.adjacency[from].iter()
+            .find(|(target, _)| *target == to)
+            .map(|(_, weight)| weight)
+    }
+
+    pub fn neighbours(&self, id: NodeId) -> impl Iterator<Item=(NodeId, &W)> + '_ {
+        return self.adjacency[id].iter().map(|(target, weight)| (*target, weight))
+    }
+
+    pub fn degree(&self, id: NodeId) -> usize {
+        return self.adjacency[id].len()
+    }
+
+    // Undirected edges are reported in both directions
+    pub fn edges(&self) -> impl Iterator<Item=(NodeId, NodeId, &W)> + '_ {
+        return self.adjacency.iter().enumerate()
+            .flat_map(|(from, arcs)| arcs.iter().map(move |(to, weight)| (from, *to, weight)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interning() {
+        let mut graph: Graph<usize, ()> = Graph::directed();
+        let a = graph.intern("a", || 1);
+        let b = graph.intern("b", || 2);
+        assert_eq!(graph.intern("a", || 42), a);
+        assert_eq!(graph.n_nodes(), 2);
+        assert_eq!(graph.node_id("b"), Some(b));
+        assert_eq!(graph.node_id("c"), None);
+        assert_eq!(graph.name(b), "b");
+        assert_eq!(*graph.attribute(a), 1);
+        *graph.attribute_mut(a) += 10;
+        assert_eq!(*graph.attribute(a), 11);
+        assert_eq!(graph.node_ids().collect::<Vec<NodeId>>(), vec![a, b]);
+    }
+
+    #[test]
+    fn test_directed_edges() {
+        let mut graph: Graph<(), u32> = Graph::directed();
+        let a = graph.intern("a", || ());
+        let b = graph.intern("b", || ());
+        graph.add_edge(a, b, 3);
+        assert!(graph.has_edge(a, b));
+        assert!(!graph.has_edge(b, a));
+        graph.add_edge(a, b, 5);
+        assert_eq!(graph.edge_weight(a, b), Some(&5));
+        assert_eq!(graph.degree(a), 1);
+        assert_eq!(graph.neighbours(a).collect::<Vec<(NodeId, &u32)>>(), vec![(b, &5)]);
+        assert_eq!(graph.neighbours(b).count(), 0);
+    }
+
+    #[test]
+    fn test_undirected_edges() {
+        let mut graph: Graph<(), u32> = Graph::undirected();
+        let a = graph.intern("a", || ());
+        let b = graph.intern("b", || ());
+        let c = graph.intern("c", || ());
+        graph.add_edge(a, b, 1);
+        graph.add_edge(c, b, 2);
+        assert!(!graph.is_directed());
+        assert_eq!(graph.edge_weight(b, a), Some(&1));
+        assert_eq!(graph.edge_weight(b, c), Some(&2));
+        assert_eq!(graph.degree(b), 2);
+        assert_eq!(graph.edges().count(), 4);
+    }
+}
@@ -1,30 +1,87 @@
 use std::str::FromStr;
 use std::cmp::min;
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
+use std::iter::Sum;
+use super::point::Point2;
+
+pub const ORTHOGONAL_OFFSETS: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+pub const DIAGONAL_OFFSETS: [(i32, i32); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
 
 #[derive(Debug, Eq, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "MatrixFields<T>"))]
 pub struct Matrix<T> {
     data: Vec<Vec<T>>,
     n_rows: usize,
     n_columns: usize
 }
 
+// Deserialized fields are only turned into a Matrix once the dimensions add up
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct MatrixFields<T> {
+    data: Vec<Vec<T>>,
+    n_rows: usize,
+    n_columns: usize
+}
+
+#[cfg(feature = "serde")]
+impl<T> TryFrom<MatrixFields<T>> for Matrix<T> {
+    type Error = String;
+
+    fn try_from(fields: MatrixFields<T>) -> Result<Matrix<T>, String> {
+        if fields.data.len() != fields.n_rows {
+            return Err(format!("Expected {} rows, found {}", fields.n_rows, fields.data.len()))
+        }
+        if let Some(y) = fields.data.iter().position(|row| row.len() != fields.n_columns) {
+            return Err(format!("Row {} has {} values, expected {}", y, fields.data[y].len(), fields.n_columns))
+        }
+        return Ok(Matrix { data: fields.data, n_rows: fields.n_rows, n_columns: fields.n_columns })
+    }
+}
+
 impl<T> Matrix<T>
     where T: FromStr + Clone, <T as FromStr>::Err: std::fmt::Debug {
     pub fn from_lines(rows: &Vec<String>) -> Matrix<T> {
-        let n_rows = rows.len();
-        let n_columns = rows.first().unwrap().len();
-        let data: Vec<Vec<T>> = rows.iter()
-            .map(
-                |row|
-                    row.split("").filter(|x|x != &"")
-                        .map(|height| height.parse::<T>().unwrap())
-                        .collect::<Vec<T>>()
-            )
-            .collect();
+        return Matrix::try_from_lines(rows).unwrap_or_else(|e| panic!("{}", e))
+    }
 
+    // One value per character, one row per line
+    pub fn try_from_lines(rows: &Vec<String>) -> Result<Matrix<T>, String> {
+        if rows.is_empty() {
+            return Err("There are no rows".to_string())
+        }
+        let mut data = Vec::with_capacity(rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            let values: Result<Vec<T>, String> = row.chars()
+                .map(|c| c.to_string().parse::<T>()
+                    .map_err(|e| format!("Cannot parse {:?} at row {}: {:?}", c, y, e)))
+                .collect();
+            data.push(values?)
+        }
+        if let Some(y) = data.iter().position(|row| row.len() != data[0].len()) {
+            return Err(format!("Row {} has {} values, expected {}", y, data[y].len(), data[0].len()))
+        }
+        let (n_rows, n_columns) = (data.len(), data[0].len());
+        return Ok(Matrix { data, n_rows, n_columns })
+    }
+}
+
+impl<T: Clone> Matrix<T> {
+    pub fn from_rows(data: Vec<Vec<T>>) -> Matrix<T> {
+        let n_rows = data.len();
+        let n_columns = data.first().map(|row| row.len()).unwrap_or(0);
+        if data.iter().any(|row| row.len() != n_columns) {
+            panic!("All rows must have {} columns", n_columns)
+        }
         return Matrix { data, n_rows, n_columns }
     }
 
+    pub fn filled(n_rows: usize, n_columns: usize, value: T) -> Matrix<T> {
+        return Matrix { data: vec![vec![value; n_columns]; n_rows], n_rows, n_columns }
+    }
+
     pub fn get_point(&self, x: i32, y: i32) -> Option<T> {
         if !Matrix::index_exists(self, x, y) { return None };
         let (x_, y_) = (x as usize, y as usize);
@@ -37,6 +94,21 @@ impl<T> Matrix<T>
         return self.data.get_mut(y_).unwrap().get_mut(x_)
     }
 
+    pub fn get<P: Into<Point2>>(&self, point: P) -> Option<T> {
+        let p = point.into();
+        return self.get_point(p.x, p.y)
+    }
+
+    pub fn get_mut<P: Into<Point2>>(&mut self, point: P) -> Option<&mut T> {
+        let p = point.into();
+        return self.get_mut_point(p.x, p.y)
+    }
+
+    pub fn contains<P: Into<Point2>>(&self, point: P) -> bool {
+        let p = point.into();
+        return self.index_exists(p.x, p.y)
+    }
+
     pub fn index_exists(&self, x: i32, y: i32) -> bool {
         if x < 0 || y < 0 { return false };
         let (x_, y_) = (x as usize, y as usize);
@@ -44,6 +116,18 @@ impl<T> Matrix<T>
         return true
     }
 
+    pub fn view(&self, mode: EdgeMode<T>) -> MatrixView<'_, T> {
+        return MatrixView { matrix: self, mode }
+    }
+
+    pub fn neighbours(&self, x: i32, y: i32, with_diagonals: bool) -> Vec<((i32, i32), T)> {
+        return self.view(EdgeMode::Bounded).neighbours(x, y, with_diagonals)
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        return &self.data[y]
+    }
+
     pub fn n_rows(&self) -> usize {
         return self.n_rows
     }
@@ -84,6 +168,181 @@ impl<T> Matrix<T>
     }
 }
 
+impl<T: Clone> Matrix<T> {
+    pub fn map<U, F>(&self, f: F) -> Matrix<U> where F: Fn(&T) -> U {
+        let data = self.data.iter()
+            .map(|row| row.iter().map(&f).collect())
+            .collect();
+        return Matrix { data, n_rows: self.n_rows, n_columns: self.n_columns }
+    }
+
+    pub fn map_in_place<F>(&mut self, f: F) where F: Fn(&mut T) {
+        for row in self.data.iter_mut() {
+            for value in row.iter_mut() { f(value) }
+        }
+    }
+
+    pub fn zip_with<U, V, F>(&self, other: &Matrix<U>, f: F) -> Matrix<V> where F: Fn(&T, &U) -> V {
+        if self.n_rows != other.n_rows || self.n_columns != other.n_columns {
+            panic!(
+                "Dimensions differ: {}x{} vs {}x{}",
+                self.n_rows, self.n_columns, other.n_rows, other.n_columns
+            )
+        }
+        let data = self.data.iter().zip(other.data.iter())
+            .map(|(row, other_row)| row.iter().zip(other_row.iter()).map(|(a, b)| f(a, b)).collect())
+            .collect();
+        return Matrix { data, n_rows: self.n_rows, n_columns: self.n_columns }
+    }
+
+    pub fn fold<A, F>(&self, init: A, f: F) -> A where F: Fn(A, &T) -> A {
+        return self.data.iter().flat_map(|row| row.iter()).fold(init, f)
+    }
+
+    pub fn count_where<P>(&self, predicate: P) -> usize where P: Fn(&T) -> bool {
+        return self.data.iter().flat_map(|row| row.iter()).filter(|v| predicate(v)).count()
+    }
+
+    pub fn positions_where<P>(&self, predicate: P) -> Vec<(i32, i32)> where P: Fn(&T) -> bool {
+        let mut positions = Vec::new();
+        for (y, row) in self.data.iter().enumerate() {
+            for (x, value) in row.iter().enumerate() {
+                if predicate(value) { positions.push((x as i32, y as i32)) }
+            }
+        }
+        return positions
+    }
+
+    // Ties are resolved in favour of the first cell in row-major order
+    pub fn min_with_position(&self) -> Option<((i32, i32), T)> where T: Ord {
+        return self._extreme_with_position(|candidate, best| candidate < best)
+    }
+
+    pub fn max_with_position(&self) -> Option<((i32, i32), T)> where T: Ord {
+        return self._extreme_with_position(|candidate, best| candidate > best)
+    }
+
+    fn _extreme_with_position<F>(&self, is_better: F) -> Option<((i32, i32), T)>
+        where F: Fn(&T, &T) -> bool {
+        let mut best: Option<((i32, i32), &T)> = None;
+        for (y, row) in self.data.iter().enumerate() {
+            for (x, value) in row.iter().enumerate() {
+                let replace = match best {
+                    Some((_, best_value)) => is_better(value, best_value),
+                    None => true
+                };
+                if replace { best = Some(((x as i32, y as i32), value)) }
+            }
+        }
+        return best.map(|(position, value)| (position, value.clone()))
+    }
+
+    pub fn row_sums(&self) -> Vec<T> where T: Sum<T> {
+        return self.data.iter().map(|row| row.iter().cloned().sum()).collect()
+    }
+
+    pub fn column_sums(&self) -> Vec<T> where T: Sum<T> {
+        return (0..self.n_columns)
+            .map(|x| self.data.iter().map(|row| row[x].clone()).sum())
+            .collect()
+    }
+
+    pub fn windows(&self, n_rows: usize, n_columns: usize) -> impl Iterator<Item=((i32, i32), Matrix<T>)> + '_ {
+        let last_y = (self.n_rows + 1).saturating_sub(n_rows);
+        let last_x = (self.n_columns + 1).saturating_sub(n_columns);
+        return (0..last_y)
+            .flat_map(move |y| (0..last_x).map(move |x| (x, y)))
+            .map(move |(x, y)| {
+                let data = self.data[y..(y + n_rows)].iter()
+                    .map(|row| row[x..(x + n_columns)].to_vec())
+                    .collect();
+                ((x as i32, y as i32), Matrix { data, n_rows, n_columns })
+            })
+    }
+
+    // The kernel is flipped in both directions and centred on every cell, so kernel
+    // entry (k_x, k_y) weighs the cell (x + centre_x - k_x, y + centre_y - k_y). Cells
+    // outside are resolved by the edge mode and skipped when the mode yields no value.
+    pub fn convolve(&self, kernel: &Matrix<i64>, mode: EdgeMode<T>) -> Matrix<i64>
+        where T: Copy + Into<i64> {
+        let view = self.view(mode);
+        let (centre_x, centre_y) = ((kernel.n_columns / 2) as i32, (kernel.n_rows / 2) as i32);
+        let mut data = Vec::with_capacity(self.n_rows);
+        for y in 0..(self.n_rows as i32) {
+            let mut row = Vec::with_capacity(self.n_columns);
+            for x in 0..(self.n_columns as i32) {
+                let mut total = 0i64;
+                for (k_y, kernel_row) in kernel.data.iter().enumerate() {
+                    for (k_x, weight) in kernel_row.iter().enumerate() {
+                        let (x_s, y_s) = (x + centre_x - k_x as i32, y + centre_y - k_y as i32);
+                        if let Some(value) = view.get_point(x_s, y_s) {
+                            total += weight * value.into()
+                        }
+                    }
+                }
+                row.push(total)
+            }
+            data.push(row)
+        }
+        return Matrix { data, n_rows: self.n_rows, n_columns: self.n_columns }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum EdgeMode<T> {
+    Bounded,
+    Wrap,
+    Clamp,
+    Default(T)
+}
+
+pub struct MatrixView<'a, T> {
+    matrix: &'a Matrix<T>,
+    mode: EdgeMode<T>
+}
+
+impl<'a, T: Clone> MatrixView<'a, T> {
+    pub fn mode(&self) -> &EdgeMode<T> {
+        return &self.mode
+    }
+
+    pub fn resolve(&self, x: i32, y: i32) -> Option<(i32, i32)> {
+        if self.matrix.index_exists(x, y) { return Some((x, y)) }
+        if self.matrix.n_rows == 0 || self.matrix.n_columns == 0 { return None }
+        let (n_columns, n_rows) = (self.matrix.n_columns as i32, self.matrix.n_rows as i32);
+        return match self.mode {
+            EdgeMode::Bounded | EdgeMode::Default(_) => None,
+            EdgeMode::Wrap => Some((modulus!(x, n_columns), modulus!(y, n_rows))),
+            EdgeMode::Clamp => Some((x.clamp(0, n_columns - 1), y.clamp(0, n_rows - 1)))
+        }
+    }
+
+    pub fn get_point(&self, x: i32, y: i32) -> Option<T> {
+        return match (self.resolve(x, y), &self.mode) {
+            (Some((x_, y_)), _) => self.matrix.get_point(x_, y_),
+            (None, EdgeMode::Default(value)) => Some(value.clone()),
+            (None, _) => None
+        }
+    }
+
+    // Wrapped and clamped neighbours are reported at their in-bounds coordinates,
+    // neighbours falling back to the default value keep their outside coordinates
+    pub fn neighbours(&self, x: i32, y: i32, with_diagonals: bool) -> Vec<((i32, i32), T)> {
+        let offsets = ORTHOGONAL_OFFSETS.iter()
+            .chain(DIAGONAL_OFFSETS.iter().filter(|_| with_diagonals));
+
+        let mut neighbours = Vec::new();
+        for (dx, dy) in offsets {
+            let (x_n, y_n) = (x + dx, y + dy);
+            let coordinates = self.resolve(x_n, y_n).unwrap_or((x_n, y_n));
+            if let Some(value) = self.get_point(x_n, y_n) {
+                neighbours.push((coordinates, value))
+            }
+        }
+        return neighbours
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -117,6 +376,26 @@ mod tests {
         assert_eq!(m.points_iter().sum::<u32>(), 421);
         assert!(m.index_exists(1, 1));
         assert!(!m.index_exists(100, 100));
+
+        assert_eq!(m.get(Point2::new(1, 1)), Some(42));
+        assert_eq!(m.get((2usize, 0usize)), Some(6));
+        assert_eq!(m.get((-1, 0)), None);
+        *m.get_mut((0u32, 0u32)).unwrap() = 5;
+        assert_eq!(m.get_point(0, 0), Some(5));
+        assert!(m.contains((9usize, 9usize)));
+        assert!(!m.contains(Point2::new(10, 0)));
+    }
+
+    #[test]
+    fn test_try_from_lines() {
+        let m = Matrix::<u32>::try_from_lines(&strs_to_strings(&["12", "34"])).unwrap();
+        assert_eq!(m, Matrix::from_rows(vec![vec![1, 2], vec![3, 4]]));
+        assert_eq!(Matrix::<u32>::try_from_lines(&Vec::new()), Err("There are no rows".to_string()));
+        assert!(Matrix::<u32>::try_from_lines(&strs_to_strings(&["12", "3x"])).unwrap_err().contains("at row 1"));
+        assert_eq!(
+            Matrix::<u32>::try_from_lines(&strs_to_strings(&["12", "345"])),
+            Err("Row 1 has 3 values, expected 2".to_string())
+        );
     }
 
     #[test]
@@ -156,4 +435,141 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn test_edge_modes() {
+        let m = Matrix::<u32>::from_lines(&strs_to_strings(&vec![
+            "123",
+            "456"
+        ]));
+
+        let bounded = m.view(EdgeMode::Bounded);
+        assert_eq!(bounded.get_point(3, 0), None);
+        assert_eq!(bounded.get_point(1, 1), Some(5));
+
+        let wrapped = m.view(EdgeMode::Wrap);
+        assert_eq!(wrapped.get_point(3, 0), Some(1));
+        assert_eq!(wrapped.get_point(-1, -1), Some(6));
+        assert_eq!(wrapped.get_point(-7, 5), Some(6));
+        assert_eq!(wrapped.resolve(-1, 2), Some((2, 0)));
+
+        let clamped = m.view(EdgeMode::Clamp);
+        assert_eq!(clamped.get_point(10, -10), Some(3));
+        assert_eq!(clamped.get_point(-1, 10), Some(4));
+
+        let with_default = m.view(EdgeMode::Default(0));
+        assert_eq!(with_default.get_point(-1, 0), Some(0));
+        assert_eq!(with_default.get_point(2, 1), Some(6));
+        assert_eq!(with_default.resolve(-1, 0), None);
+    }
+
+    #[test]
+    fn test_neighbours_respect_edge_mode() {
+        let m = Matrix::<u32>::from_lines(&strs_to_strings(&vec![
+            "123",
+            "456",
+            "789"
+        ]));
+
+        let mut bounded = m.neighbours(0, 0, false);
+        bounded.sort();
+        assert_eq!(bounded, vec![((0, 1), 4), ((1, 0), 2)]);
+        assert_eq!(m.neighbours(1, 1, true).len(), 8);
+        assert_eq!(m.neighbours(0, 0, true).len(), 3);
+
+        let mut wrapped = m.view(EdgeMode::Wrap).neighbours(0, 0, false);
+        wrapped.sort();
+        assert_eq!(wrapped, vec![((0, 1), 4), ((0, 2), 7), ((1, 0), 2), ((2, 0), 3)]);
+        assert_eq!(m.view(EdgeMode::Wrap).neighbours(0, 0, true).len(), 8);
+
+        let mut clamped = m.view(EdgeMode::Clamp).neighbours(0, 0, false);
+        clamped.sort();
+        assert_eq!(clamped, vec![((0, 0), 1), ((0, 0), 1), ((0, 1), 4), ((1, 0), 2)]);
+
+        let with_default = m.view(EdgeMode::Default(0)).neighbours(0, 0, true);
+        assert_eq!(with_default.len(), 8);
+        assert_eq!(with_default.iter().filter(|(_, v)| *v == 0).count(), 5);
+        assert!(with_default.contains(&((-1, -1), 0)));
+    }
+
+    #[test]
+    fn test_element_wise_operations() {
+        let mut m = Matrix::<u32>::from_lines(&strs_to_strings(&vec![
+            "123",
+            "456"
+        ]));
+        assert_eq!(m.map(|v| *v % 2 == 0), Matrix::from_rows(vec![
+            vec![false, true, false],
+            vec![true, false, true]
+        ]));
+        assert_eq!(m.fold(0, |acc, v| acc + v), 21);
+        assert_eq!(m.count_where(|v| *v > 2), 4);
+        assert_eq!(m.positions_where(|v| *v % 3 == 0), vec![(2, 0), (2, 1)]);
+        assert_eq!(m.row_sums(), vec![6, 15]);
+        assert_eq!(m.column_sums(), vec![5, 7, 9]);
+        assert_eq!(m.min_with_position(), Some(((0, 0), 1)));
+        assert_eq!(m.max_with_position(), Some(((2, 1), 6)));
+
+        let other = m.map(|v| *v as i64 * 10);
+        assert_eq!(
+            m.zip_with(&other, |a, b| *a as i64 + b),
+            Matrix::from_rows(vec![vec![11, 22, 33], vec![44, 55, 66]])
+        );
+
+        m.map_in_place(|v| *v = 9 - *v);
+        assert_eq!(m, Matrix::<u32>::from_lines(&strs_to_strings(&vec![
+            "876",
+            "543"
+        ])));
+        assert_eq!(Matrix::<u32>::filled(0, 0, 0).max_with_position(), None);
+        assert_eq!(Matrix::<u32>::filled(2, 2, 7).max_with_position(), Some(((0, 0), 7)));
+    }
+
+    #[test]
+    fn test_windows() {
+        let m = Matrix::<u32>::from_lines(&strs_to_strings(&vec![
+            "123",
+            "456",
+            "789"
+        ]));
+        let windows: Vec<((i32, i32), Matrix<u32>)> = m.windows(2, 2).collect();
+        assert_eq!(windows.len(), 4);
+        assert_eq!(windows[3], ((1, 1), Matrix::<u32>::from_lines(&strs_to_strings(&vec![
+            "56",
+            "89"
+        ]))));
+        assert_eq!(m.windows(1, 3).map(|(_, w)| w.row_sums()[0]).collect::<Vec<u32>>(), vec![6, 15, 24]);
+        assert_eq!(m.windows(4, 1).count(), 0);
+    }
+
+    #[test]
+    fn test_convolve() {
+        let m = Matrix::<u32>::from_lines(&strs_to_strings(&vec![
+            "123",
+            "456",
+            "789"
+        ]));
+        let box_kernel = Matrix::filled(3, 3, 1i64);
+        assert_eq!(
+            m.convolve(&box_kernel, EdgeMode::Bounded),
+            Matrix::from_rows(vec![vec![12, 21, 16], vec![27, 45, 33], vec![24, 39, 28]])
+        );
+        assert_eq!(m.convolve(&box_kernel, EdgeMode::Wrap).get_point(0, 0), Some(45));
+        assert_eq!(m.convolve(&box_kernel, EdgeMode::Default(10)).get_point(0, 0), Some(62));
+
+        let difference = Matrix::from_rows(vec![vec![-1i64, 1]]);
+        assert_eq!(
+            m.convolve(&difference, EdgeMode::Clamp),
+            Matrix::from_rows(vec![vec![-1, -1, 0], vec![-1, -1, 0], vec![-1, -1, 0]])
+        );
+
+        // Convolving with a shifted impulse moves the image the same way
+        let shift_right = Matrix::from_rows(vec![vec![0i64, 0, 1]]);
+        assert_eq!(
+            m.convolve(&shift_right, EdgeMode::Default(0)),
+            Matrix::from_rows(vec![vec![0, 1, 2], vec![0, 4, 5], vec![0, 7, 8]])
+        );
+        let shift_up = Matrix::from_rows(vec![vec![1i64], vec![0], vec![0]]);
+        assert_eq!(m.convolve(&shift_up, EdgeMode::Default(0)).row(0), &[4, 5, 6]);
+    }
 }
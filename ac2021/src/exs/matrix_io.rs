@@ -0,0 +1,293 @@
+use std::fmt::Display;
+use std::fs;
+use std::str::FromStr;
+use super::matrix::Matrix;
+
+const MAGIC: &[u8; 4] = b"MTRX";
+const VERSION: u8 = 1;
+const HEADER_LEN: usize = 4 + 1 + 1 + 1 + 4 + 4;
+
+pub trait BinaryElement: Sized {
+    const TYPE_TAG: u8;
+    const SIZE: usize;
+    fn write_to(&self, out: &mut Vec<u8>);
+    fn read_from(bytes: &[u8]) -> Self;
+}
+
+macro_rules! binary_element {
+    ($t:ty, $tag:expr) => {
+        impl BinaryElement for $t {
+            const TYPE_TAG: u8 = $tag;
+            const SIZE: usize = std::mem::size_of::<$t>();
+
+            fn write_to(&self, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.to_le_bytes())
+            }
+
+            fn read_from(bytes: &[u8]) -> $t {
+                let mut buf = [0u8; std::mem::size_of::<$t>()];
+                buf.copy_from_slice(bytes);
+                return <$t>::from_le_bytes(buf)
+            }
+        }
+    }
+}
+
+binary_element!(u8, 1);
+binary_element!(u16, 2);
+binary_element!(u32, 3);
+binary_element!(u64, 4);
+binary_element!(i32, 5);
+binary_element!(i64, 6);
+
+impl BinaryElement for bool {
+    const TYPE_TAG: u8 = 7;
+    const SIZE: usize = 1;
+
+    fn write_to(&self, out: &mut Vec<u8>) {
+        out.push(*self as u8)
+    }
+
+    fn read_from(bytes: &[u8]) -> bool {
+        return bytes[0] != 0
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MatrixFormat {
+    Digits,
+    Delimited(String),
+    Binary
+}
+
+impl FromStr for MatrixFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<MatrixFormat, String> {
+        return match s {
+            "digits" => Ok(MatrixFormat::Digits),
+            "csv" => Ok(MatrixFormat::Delimited(",".to_string())),
+            "binary" => Ok(MatrixFormat::Binary),
+            format => Err(format!("Unknown matrix format {:?}, expected digits, csv or binary", format))
+        }
+    }
+}
+
+impl<T: Clone> Matrix<T> {
+    // Same layout as `from_lines` reads: one character per value, one line per row
+    pub fn to_lines(&self) -> Result<Vec<String>, String> where T: Display {
+        let mut lines = Vec::with_capacity(self.n_rows());
+        for y in 0..self.n_rows() {
+            let mut line = String::with_capacity(self.n_columns());
+            for value in self.row(y) {
+                let encoded = value.to_string();
+                if encoded.chars().count() != 1 {
+                    return Err(format!("Value {} at row {} does not fit in one character", encoded, y))
+                }
+                line.push_str(&encoded)
+            }
+            lines.push(line)
+        }
+        return Ok(lines)
+    }
+
+    pub fn to_delimited(&self, separator: &str) -> Vec<String> where T: Display {
+        return (0..self.n_rows()).map(|y|
+            self.row(y).iter().map(|v| v.to_string()).collect::<Vec<String>>().join(separator)
+        ).collect()
+    }
+
+    pub fn from_delimited(rows: &Vec<String>, separator: &str) -> Result<Matrix<T>, String>
+        where T: FromStr, <T as FromStr>::Err: std::fmt::Debug {
+        let mut data = Vec::with_capacity(rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            let values: Result<Vec<T>, String> = row.split(separator)
+                .map(|v| v.trim().parse::<T>()
+                    .map_err(|e| format!("Cannot parse {:?} at row {}: {:?}", v, y, e)))
+                .collect();
+            data.push(values?)
+        }
+        if let Some(y) = data.iter().position(|row| row.len() != data[0].len()) {
+            return Err(format!("Row {} has {} values, expected {}", y, data[y].len(), data[0].len()))
+        }
+        return Ok(Matrix::from_rows(data))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> where T: BinaryElement {
+        let mut bytes = Vec::with_capacity(HEADER_LEN + self.n_rows() * self.n_columns() * T::SIZE);
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.push(T::TYPE_TAG);
+        bytes.push(T::SIZE as u8);
+        bytes.extend_from_slice(&(self.n_rows() as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.n_columns() as u32).to_le_bytes());
+        for value in self.points_iter() {
+            value.write_to(&mut bytes)
+        }
+        return bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Matrix<T>, String> where T: BinaryElement {
+        if bytes.len() < HEADER_LEN || &bytes[0..4] != MAGIC {
+            return Err("Not a matrix snapshot".to_string())
+        }
+        if bytes[4] != VERSION {
+            return Err(format!("Unsupported snapshot version {}", bytes[4]))
+        }
+        if bytes[5] != T::TYPE_TAG || bytes[6] as usize != T::SIZE {
+            return Err(format!(
+                "Snapshot holds elements of type {} and size {}, expected type {} and size {}",
+                bytes[5], bytes[6], T::TYPE_TAG, T::SIZE
+            ))
+        }
+        let read_u32 = |at: usize| {
+            let mut buf = [0u8; 4];
+            buf.copy_from_slice(&bytes[at..(at + 4)]);
+            u32::from_le_bytes(buf) as usize
+        };
+        let (n_rows, n_columns) = (read_u32(7), read_u32(11));
+        let payload = &bytes[HEADER_LEN..];
+        if payload.len() != n_rows * n_columns * T::SIZE {
+            return Err(format!(
+                "Expected {} bytes of data for {}x{} matrix, found {}",
+                n_rows * n_columns * T::SIZE, n_rows, n_columns, payload.len()
+            ))
+        }
+        if n_columns == 0 { return Ok(Matrix::from_rows(vec![Vec::new(); n_rows])) }
+        let data = payload.chunks(n_columns * T::SIZE)
+            .map(|row| row.chunks(T::SIZE).map(T::read_from).collect())
+            .collect();
+        return Ok(Matrix::from_rows(data))
+    }
+
+    pub fn save(&self, file_path: &str, format: &MatrixFormat) -> Result<(), String>
+        where T: Display + BinaryElement {
+        let bytes = match format {
+            MatrixFormat::Digits => (self.to_lines()?.join("\n") + "\n").into_bytes(),
+            MatrixFormat::Delimited(separator) =>
+                (self.to_delimited(separator).join("\n") + "\n").into_bytes(),
+            MatrixFormat::Binary => self.to_bytes()
+        };
+        return fs::write(file_path, bytes).map_err(|e| format!("Cannot write {}: {}", file_path, e))
+    }
+
+    pub fn load(file_path: &str, format: &MatrixFormat) -> Result<Matrix<T>, String>
+        where T: FromStr + BinaryElement, <T as FromStr>::Err: std::fmt::Debug {
+        let read_lines = || fs::read_to_string(file_path)
+            .map(|content| content.lines().map(|line| line.to_string()).collect::<Vec<String>>())
+            .map_err(|e| format!("Cannot read {}: {}", file_path, e));
+        return match format {
+            MatrixFormat::Digits => Matrix::try_from_lines(&read_lines()?),
+            MatrixFormat::Delimited(separator) => Matrix::from_delimited(&read_lines()?, separator),
+            MatrixFormat::Binary => {
+                let bytes = fs::read(file_path)
+                    .map_err(|e| format!("Cannot read {}: {}", file_path, e))?;
+                Matrix::from_bytes(&bytes)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use crate::exs::utils::strs_to_strings;
+
+    fn sample() -> Matrix<u32> {
+        return Matrix::<u32>::from_lines(&strs_to_strings(&[
+            "5483",
+            "2745",
+            "5264"
+        ]))
+    }
+
+    #[test]
+    fn test_digits_round_trip() {
+        let m = sample();
+        let lines = m.to_lines().unwrap();
+        assert_eq!(lines, strs_to_strings(&["5483", "2745", "5264"]));
+        assert_eq!(Matrix::<u32>::from_lines(&lines), m);
+        assert!(m.map(|v| v * 10).to_lines().is_err());
+    }
+
+    #[test]
+    fn test_delimited_round_trip() {
+        let m = sample().map(|v| v * 100);
+        let lines = m.to_delimited(",");
+        assert_eq!(lines[0], "500,400,800,300");
+        assert_eq!(Matrix::<u32>::from_delimited(&lines, ",").unwrap(), m);
+        assert!(Matrix::<u32>::from_delimited(&strs_to_strings(&["1,2", "3"]), ",").is_err());
+        assert!(Matrix::<u32>::from_delimited(&strs_to_strings(&["1,x"]), ",").is_err());
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        let m = sample();
+        let bytes = m.to_bytes();
+        assert_eq!(&bytes[0..4], b"MTRX");
+        assert_eq!(bytes.len(), HEADER_LEN + 12 * 4);
+        assert_eq!(Matrix::<u32>::from_bytes(&bytes).unwrap(), m);
+
+        let flags = m.map(|v| *v > 4);
+        assert_eq!(Matrix::<bool>::from_bytes(&flags.to_bytes()).unwrap(), flags);
+        let negatives = m.map(|v| -(*v as i64));
+        assert_eq!(Matrix::<i64>::from_bytes(&negatives.to_bytes()).unwrap(), negatives);
+
+        assert!(Matrix::<u64>::from_bytes(&bytes).is_err());
+        assert!(Matrix::<u32>::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Matrix::<u32>::from_bytes(b"nope").is_err());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let m = sample();
+        let formats = [
+            MatrixFormat::Digits,
+            MatrixFormat::Delimited(" ".to_string()),
+            MatrixFormat::Binary
+        ];
+        for (i, format) in formats.iter().enumerate() {
+            let path = env::temp_dir().join(format!("matrix-io-test-{}-{}", std::process::id(), i));
+            let path = path.to_str().unwrap();
+            m.save(path, format).unwrap();
+            assert_eq!(Matrix::<u32>::load(path, format).unwrap(), m);
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!("digits".parse::<MatrixFormat>(), Ok(MatrixFormat::Digits));
+        assert_eq!("csv".parse::<MatrixFormat>(), Ok(MatrixFormat::Delimited(",".to_string())));
+        assert_eq!("binary".parse::<MatrixFormat>(), Ok(MatrixFormat::Binary));
+        assert!("json".parse::<MatrixFormat>().is_err());
+    }
+
+    #[test]
+    fn test_load_invalid_digits() {
+        let path = env::temp_dir().join(format!("matrix-io-test-{}-invalid", std::process::id()));
+        let path = path.to_str().unwrap();
+        for content in ["", "12\n3a\n"].iter() {
+            fs::write(path, content).unwrap();
+            assert!(Matrix::<u32>::load(path, &MatrixFormat::Digits).is_err());
+        }
+        fs::remove_file(path).unwrap();
+        assert!(Matrix::<u32>::load(path, &MatrixFormat::Digits).unwrap_err().starts_with("Cannot read"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let m = sample();
+        let encoded = serde_json::to_string(&m).unwrap();
+        assert_eq!(serde_json::from_str::<Matrix<u32>>(&encoded).unwrap(), m);
+
+        let ragged = r#"{"data": [[1, 2], [3]], "n_rows": 2, "n_columns": 2}"#;
+        assert!(serde_json::from_str::<Matrix<u32>>(ragged).is_err());
+        let wrong_rows = r#"{"data": [[1, 2]], "n_rows": 2, "n_columns": 2}"#;
+        assert!(serde_json::from_str::<Matrix<u32>>(wrong_rows).is_err());
+        let wrong_columns = r#"{"data": [[1, 2]], "n_rows": 1, "n_columns": 3}"#;
+        assert!(serde_json::from_str::<Matrix<u32>>(wrong_columns).is_err());
+    }
+}
@@ -15,3 +15,14 @@ pub mod day_13_transparent_origami;
 pub mod day_14_extended_polymerization;
 pub mod day_15_chiton;
 pub mod matrix;
+pub mod sparse_grid;
+pub mod components;
+pub mod render;
+pub mod bit_grid;
+pub mod point;
+pub mod matrix_io;
+pub mod graph;
+pub mod movement;
+pub mod distance;
+pub mod window;
+pub mod sonar_stats;
@@ -0,0 +1,180 @@
+use std::rc::Rc;
+use super::matrix::{Matrix, DIAGONAL_OFFSETS, ORTHOGONAL_OFFSETS};
+
+pub const KNIGHT_OFFSETS: [(i32, i32); 8] = [
+    (1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2)
+];
+
+// Cost of entering a cell given the value moved from and the value moved to,
+// None when the cell cannot be entered at all
+pub type EntryCost<T> = Rc<dyn Fn(&T, &T) -> Option<u64>>;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Move {
+    pub dx: i32,
+    pub dy: i32,
+    pub multiplier: u64
+}
+
+// Which moves a grid search may make from a cell and what each of them costs:
+// the entry cost of the target cell times the multiplier of the move
+#[derive(Clone)]
+pub struct MovementModel<T> {
+    moves: Vec<Move>,
+    entry_cost: EntryCost<T>,
+    custom_cost: bool
+}
+
+impl<T: Copy + Into<u64> + 'static> MovementModel<T> {
+    // By default entering a cell costs its value
+    pub fn stencil(moves: Vec<Move>) -> MovementModel<T> {
+        return MovementModel { moves, entry_cost: Rc::new(|_, to| Some((*to).into())), custom_cost: false }
+    }
+
+    pub fn orthogonal() -> MovementModel<T> {
+        return MovementModel::stencil(MovementModel::<T>::_moves(&ORTHOGONAL_OFFSETS, 1))
+    }
+
+    pub fn eight_way(diagonal_multiplier: u64) -> MovementModel<T> {
+        let mut moves = MovementModel::<T>::_moves(&ORTHOGONAL_OFFSETS, 1);
+        moves.extend(MovementModel::<T>::_moves(&DIAGONAL_OFFSETS, diagonal_multiplier));
+        return MovementModel::stencil(moves)
+    }
+
+    pub fn king() -> MovementModel<T> {
+        return MovementModel::eight_way(1)
+    }
+
+    pub fn knight() -> MovementModel<T> {
+        return MovementModel::stencil(MovementModel::<T>::_moves(&KNIGHT_OFFSETS, 1))
+    }
+
+    // "orthogonal", "king", "knight" or "eight-way:<diagonal multiplier>", optionally
+    // followed by ",walls-above:<value>" to stop cells holding more than the value
+    // from being entered
+    pub fn parse(encoded: &str) -> Result<MovementModel<T>, String> {
+        let mut parts = encoded.split(',');
+        let model = match parts.next().unwrap().split(':').collect::<Vec<&str>>().as_slice() {
+            ["orthogonal"] => MovementModel::orthogonal(),
+            ["king"] => MovementModel::king(),
+            ["knight"] => MovementModel::knight(),
+            ["eight-way", multiplier] => MovementModel::eight_way(multiplier.parse::<u64>()
+                .map_err(|_| format!("Diagonal multiplier {:?} is not a non-negative integer", multiplier))?),
+            _ => return Err(format!("Unknown movement model {:?}", encoded))
+        };
+        return match parts.collect::<Vec<&str>>().as_slice() {
+            [] => Ok(model),
+            [walls] => match walls.strip_prefix("walls-above:").map(|v| v.parse::<u64>()) {
+                Some(Ok(max_value)) => Ok(model.with_walls(move |v: &T| (*v).into() > max_value)),
+                _ => Err(format!("Expected walls-above:<value>, found {:?}", walls))
+            },
+            _ => Err(format!("Unknown movement model {:?}", encoded))
+        }
+    }
+
+    fn _moves(offsets: &[(i32, i32)], multiplier: u64) -> Vec<Move> {
+        return offsets.iter().map(|(dx, dy)| Move { dx: *dx, dy: *dy, multiplier }).collect()
+    }
+}
+
+impl<T: Clone + 'static> MovementModel<T> {
+    pub fn with_entry_cost<F>(mut self, entry_cost: F) -> MovementModel<T>
+        where F: Fn(&T, &T) -> Option<u64> + 'static {
+        self.entry_cost = Rc::new(entry_cost);
+        self.custom_cost = true;
+        return self
+    }
+
+    // Cells matching `is_wall` cannot be entered, other cells keep their entry cost
+    pub fn with_walls<F>(mut self, is_wall: F) -> MovementModel<T> where F: Fn(&T) -> bool + 'static {
+        let entry_cost = self.entry_cost.clone();
+        self.entry_cost = Rc::new(move |from, to| if is_wall(to) { None } else { entry_cost(from, to) });
+        return self
+    }
+
+    pub fn moves(&self) -> &[Move] {
+        return &self.moves
+    }
+
+    // Up, down, left and right moves costing the value of the entered cell, as in
+    // day 15. The Manhattan distance times the smallest cell value is then a lower
+    // bound on the remaining cost, which A* relies on.
+    pub fn is_orthogonal(&self) -> bool {
+        return !self.custom_cost && self.moves.len() == 4
+            && self.moves.iter().all(|m| m.multiplier == 1 && m.dx.abs() + m.dy.abs() == 1)
+    }
+
+    pub fn successors(&self, grid: &Matrix<T>, x: i32, y: i32) -> Vec<((i32, i32), u64)> {
+        let from = match grid.get_point(x, y) {
+            Some(value) => value,
+            None => return Vec::new()
+        };
+        return self.moves.iter().filter_map(|m| {
+            let (nx, ny) = (x + m.dx, y + m.dy);
+            let to = grid.get_point(nx, ny)?;
+            (self.entry_cost)(&from, &to).map(|cost| ((nx, ny), cost * m.multiplier))
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid() -> Matrix<u32> {
+        return Matrix::from_rows(vec![
+            vec![1, 2, 3],
+            vec![4, 5, 6],
+            vec![7, 8, 9]
+        ])
+    }
+
+    #[test]
+    fn test_orthogonal() {
+        let model = MovementModel::orthogonal();
+        assert!(model.is_orthogonal());
+        assert_eq!(model.successors(&grid(), 0, 0), vec![((1, 0), 2), ((0, 1), 4)]);
+        assert_eq!(model.successors(&grid(), 1, 1).len(), 4);
+        assert!(model.successors(&grid(), 5, 5).is_empty());
+    }
+
+    #[test]
+    fn test_eight_way_and_stencils() {
+        let model = MovementModel::eight_way(3);
+        assert!(!model.is_orthogonal());
+        let successors = model.successors(&grid(), 0, 0);
+        assert_eq!(successors, vec![((1, 0), 2), ((0, 1), 4), ((1, 1), 15)]);
+        assert_eq!(MovementModel::<u32>::king().successors(&grid(), 1, 1).len(), 8);
+        assert_eq!(MovementModel::knight().successors(&grid(), 0, 0), vec![((1, 2), 8), ((2, 1), 6)]);
+
+        let long_jumps = MovementModel::stencil(vec![Move { dx: 2, dy: 0, multiplier: 10 }]);
+        assert_eq!(long_jumps.successors(&grid(), 0, 1), vec![((2, 1), 60)]);
+    }
+
+    #[test]
+    fn test_parse() {
+        let successors = |encoded: &str| MovementModel::<u32>::parse(encoded).unwrap().successors(&grid(), 1, 1).len();
+        assert_eq!(successors("orthogonal"), 4);
+        assert_eq!(successors("king"), 8);
+        assert_eq!(successors("knight"), 0);
+        assert_eq!(successors("eight-way:2,walls-above:5"), 4);
+        assert!(MovementModel::<u32>::parse("orthogonal,walls-above:5").unwrap().is_orthogonal());
+        assert_eq!(MovementModel::<u32>::parse("eight-way:3").unwrap().successors(&grid(), 0, 0)[2], ((1, 1), 15));
+        for invalid in ["rook", "eight-way", "eight-way:x", "king,walls-above:", "king,walls"].iter() {
+            assert!(MovementModel::<u32>::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_entry_costs_and_walls() {
+        let climbing = MovementModel::orthogonal()
+            .with_entry_cost(|from: &u32, to: &u32| Some((*to as i64 - *from as i64).unsigned_abs()));
+        assert!(!climbing.is_orthogonal());
+        assert_eq!(climbing.successors(&grid(), 1, 1), vec![((2, 1), 1), ((0, 1), 1), ((1, 2), 3), ((1, 0), 3)]);
+
+        let walled = MovementModel::orthogonal().with_walls(|v: &u32| *v > 5);
+        assert_eq!(walled.successors(&grid(), 1, 1), vec![((0, 1), 4), ((1, 0), 2)]);
+        let steep = climbing.with_walls(|v: &u32| *v == 4);
+        assert_eq!(steep.successors(&grid(), 0, 0), vec![((1, 0), 1)]);
+    }
+}
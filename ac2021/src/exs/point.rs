@@ -0,0 +1,311 @@
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug, Ord, PartialOrd, Default)]
+pub struct Point2 {
+    pub x: i32,
+    pub y: i32
+}
+
+impl Point2 {
+    pub fn new(x: i32, y: i32) -> Point2 {
+        return Point2 { x, y }
+    }
+
+    pub fn manhattan(&self, other: &Point2) -> u32 {
+        return ((self.x - other.x).abs() + (self.y - other.y).abs()) as u32
+    }
+
+    pub fn chebyshev(&self, other: &Point2) -> u32 {
+        return (self.x - other.x).abs().max((self.y - other.y).abs()) as u32
+    }
+
+    pub fn signum(&self) -> Point2 {
+        return Point2 { x: self.x.signum(), y: self.y.signum() }
+    }
+
+    pub fn neighbours(&self, with_diagonals: bool) -> Vec<Point2> {
+        let directions: &[Direction] = if with_diagonals { &Direction::ALL } else { &Direction::ORTHOGONAL };
+        return directions.iter().map(|d| *self + *d).collect()
+    }
+}
+
+impl From<(i32, i32)> for Point2 {
+    fn from((x, y): (i32, i32)) -> Point2 {
+        return Point2 { x, y }
+    }
+}
+
+impl From<(u32, u32)> for Point2 {
+    fn from((x, y): (u32, u32)) -> Point2 {
+        return Point2 { x: x as i32, y: y as i32 }
+    }
+}
+
+impl From<(usize, usize)> for Point2 {
+    fn from((x, y): (usize, usize)) -> Point2 {
+        return Point2 { x: x as i32, y: y as i32 }
+    }
+}
+
+impl From<Point2> for (i32, i32) {
+    fn from(p: Point2) -> (i32, i32) {
+        return (p.x, p.y)
+    }
+}
+
+impl Add for Point2 {
+    type Output = Point2;
+    fn add(self, other: Point2) -> Point2 {
+        return Point2 { x: self.x + other.x, y: self.y + other.y }
+    }
+}
+
+impl Add<Direction> for Point2 {
+    type Output = Point2;
+    fn add(self, direction: Direction) -> Point2 {
+        return self + direction.delta()
+    }
+}
+
+impl AddAssign for Point2 {
+    fn add_assign(&mut self, other: Point2) {
+        *self = *self + other
+    }
+}
+
+impl Sub for Point2 {
+    type Output = Point2;
+    fn sub(self, other: Point2) -> Point2 {
+        return Point2 { x: self.x - other.x, y: self.y - other.y }
+    }
+}
+
+impl SubAssign for Point2 {
+    fn sub_assign(&mut self, other: Point2) {
+        *self = *self - other
+    }
+}
+
+impl Mul<i32> for Point2 {
+    type Output = Point2;
+    fn mul(self, factor: i32) -> Point2 {
+        return Point2 { x: self.x * factor, y: self.y * factor }
+    }
+}
+
+impl Neg for Point2 {
+    type Output = Point2;
+    fn neg(self) -> Point2 {
+        return Point2 { x: -self.x, y: -self.y }
+    }
+}
+
+// Unlike Point2 this is not a grid index, so coordinates are wide enough for long
+// walks such as day 2 courses
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug, Ord, PartialOrd, Default)]
+pub struct Point3 {
+    pub x: i64,
+    pub y: i64,
+    pub z: i64
+}
+
+impl Point3 {
+    pub fn new(x: i64, y: i64, z: i64) -> Point3 {
+        return Point3 { x, y, z }
+    }
+
+    // None when a coordinate overflows
+    pub fn checked_add(&self, other: Point3) -> Option<Point3> {
+        return Some(Point3 {
+            x: self.x.checked_add(other.x)?,
+            y: self.y.checked_add(other.y)?,
+            z: self.z.checked_add(other.z)?
+        })
+    }
+
+    pub fn manhattan(&self, other: &Point3) -> u64 {
+        return (self.x - other.x).unsigned_abs() + (self.y - other.y).unsigned_abs() + (self.z - other.z).unsigned_abs()
+    }
+
+    pub fn chebyshev(&self, other: &Point3) -> u64 {
+        return (self.x - other.x).unsigned_abs()
+            .max((self.y - other.y).unsigned_abs())
+            .max((self.z - other.z).unsigned_abs())
+    }
+}
+
+impl From<(i64, i64, i64)> for Point3 {
+    fn from((x, y, z): (i64, i64, i64)) -> Point3 {
+        return Point3 { x, y, z }
+    }
+}
+
+impl Add for Point3 {
+    type Output = Point3;
+    fn add(self, other: Point3) -> Point3 {
+        return Point3 { x: self.x + other.x, y: self.y + other.y, z: self.z + other.z }
+    }
+}
+
+impl AddAssign for Point3 {
+    fn add_assign(&mut self, other: Point3) {
+        *self = *self + other
+    }
+}
+
+impl Sub for Point3 {
+    type Output = Point3;
+    fn sub(self, other: Point3) -> Point3 {
+        return Point3 { x: self.x - other.x, y: self.y - other.y, z: self.z - other.z }
+    }
+}
+
+impl SubAssign for Point3 {
+    fn sub_assign(&mut self, other: Point3) {
+        *self = *self - other
+    }
+}
+
+impl Mul<i64> for Point3 {
+    type Output = Point3;
+    fn mul(self, factor: i64) -> Point3 {
+        return Point3 { x: self.x * factor, y: self.y * factor, z: self.z * factor }
+    }
+}
+
+impl Neg for Point3 {
+    type Output = Point3;
+    fn neg(self) -> Point3 {
+        return Point3 { x: -self.x, y: -self.y, z: -self.z }
+    }
+}
+
+// Screen orientation: north points towards lower y, like rows of a Matrix
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug)]
+pub enum Direction {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest
+}
+
+impl Direction {
+    pub const ALL: [Direction; 8] = [
+        Direction::North,
+        Direction::NorthEast,
+        Direction::East,
+        Direction::SouthEast,
+        Direction::South,
+        Direction::SouthWest,
+        Direction::West,
+        Direction::NorthWest
+    ];
+
+    pub const ORTHOGONAL: [Direction; 4] = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West
+    ];
+
+    pub const DIAGONAL: [Direction; 4] = [
+        Direction::NorthEast,
+        Direction::SouthEast,
+        Direction::SouthWest,
+        Direction::NorthWest
+    ];
+
+    pub fn delta(&self) -> Point2 {
+        return match self {
+            Direction::North => Point2::new(0, -1),
+            Direction::NorthEast => Point2::new(1, -1),
+            Direction::East => Point2::new(1, 0),
+            Direction::SouthEast => Point2::new(1, 1),
+            Direction::South => Point2::new(0, 1),
+            Direction::SouthWest => Point2::new(-1, 1),
+            Direction::West => Point2::new(-1, 0),
+            Direction::NorthWest => Point2::new(-1, -1)
+        }
+    }
+
+    pub fn is_diagonal(&self) -> bool {
+        return Direction::DIAGONAL.contains(self)
+    }
+
+    // Clockwise by `steps` eighths of a full turn, negative steps turn counter-clockwise
+    pub fn rotate(&self, steps: i32) -> Direction {
+        let idx = Direction::ALL.iter().position(|d| d == self).unwrap() as i32;
+        return Direction::ALL[modulus!(idx + steps, 8) as usize]
+    }
+
+    pub fn turn_right(&self) -> Direction {
+        return self.rotate(2)
+    }
+
+    pub fn turn_left(&self) -> Direction {
+        return self.rotate(-2)
+    }
+
+    pub fn opposite(&self) -> Direction {
+        return self.rotate(4)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_point2_arithmetic() {
+        let a = Point2::new(1, 2);
+        let b = Point2::from((4usize, 6usize));
+        assert_eq!(a + b, Point2::new(5, 8));
+        assert_eq!(b - a, Point2::new(3, 4));
+        assert_eq!(-a, Point2::new(-1, -2));
+        assert_eq!(a * 3, Point2::new(3, 6));
+        assert_eq!(a.manhattan(&b), 7);
+        assert_eq!(a.chebyshev(&b), 4);
+        assert_eq!((b - a).signum(), Point2::new(1, 1));
+
+        let mut c = a;
+        c += Point2::new(1, 1);
+        c -= Point2::new(0, 3);
+        assert_eq!(c, Point2::new(2, 0));
+        assert_eq!(<(i32, i32)>::from(c), (2, 0));
+        assert_eq!(a.neighbours(false).len(), 4);
+        assert!(a.neighbours(true).contains(&Point2::new(0, 1)));
+    }
+
+    #[test]
+    fn test_point3_arithmetic() {
+        let a = Point3::new(1, 2, 3);
+        let b = Point3::from((-1, 0, 7));
+        assert_eq!(a + b, Point3::new(0, 2, 10));
+        assert_eq!(a - b, Point3::new(2, 2, -4));
+        assert_eq!(-a * 2, Point3::new(-2, -4, -6));
+        assert_eq!(a.manhattan(&b), 8);
+        assert_eq!(a.chebyshev(&b), 4);
+        assert_eq!(a.checked_add(b), Some(a + b));
+        assert_eq!(Point3::new(0, i64::MAX, 0).checked_add(a), None);
+    }
+
+    #[test]
+    fn test_directions() {
+        assert_eq!(Point2::new(0, 0) + Direction::North, Point2::new(0, -1));
+        assert_eq!(Direction::North.turn_right(), Direction::East);
+        assert_eq!(Direction::North.turn_left(), Direction::West);
+        assert_eq!(Direction::West.turn_right(), Direction::North);
+        assert_eq!(Direction::NorthEast.rotate(1), Direction::East);
+        assert_eq!(Direction::North.rotate(-1), Direction::NorthWest);
+        assert_eq!(Direction::SouthWest.opposite(), Direction::NorthEast);
+        assert!(Direction::SouthWest.is_diagonal());
+        assert!(!Direction::South.is_diagonal());
+        for d in Direction::ALL.iter() {
+            assert_eq!(d.delta() + d.opposite().delta(), Point2::new(0, 0));
+        }
+    }
+}
@@ -0,0 +1,385 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::File;
+use std::io::Write;
+use super::matrix::Matrix;
+
+pub type Rgb = (u8, u8, u8);
+
+// Grid values which can be coloured on a heatmap. Only their order and relative
+// distance matter, so the precision lost by wide integers does not show.
+pub trait HeatValue: Copy {
+    fn heat(&self) -> f64;
+}
+
+macro_rules! heat_value {
+    ($($t:ty),*) => {
+        $(impl HeatValue for $t {
+            fn heat(&self) -> f64 {
+                return *self as f64
+            }
+        })*
+    }
+}
+
+heat_value!(u8, u16, u32, u64, usize, i8, i16, i32, i64, f32, f64);
+
+pub fn hex_color((r, g, b): Rgb) -> String {
+    return format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+// Quoted Graphviz identifier
+pub fn dot_id(name: &str) -> String {
+    return format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Highlight {
+    pub cells: HashSet<(i32, i32)>,
+    pub marker: char,
+    pub color: Rgb
+}
+
+impl Highlight {
+    pub fn new<I>(cells: I) -> Highlight where I: IntoIterator<Item=(i32, i32)> {
+        return Highlight { cells: cells.into_iter().collect(), marker: '*', color: (255, 0, 0) }
+    }
+
+    pub fn with_marker(mut self, marker: char) -> Highlight {
+        self.marker = marker;
+        return self
+    }
+
+    pub fn with_color(mut self, color: Rgb) -> Highlight {
+        self.color = color;
+        return self
+    }
+
+    fn contains(highlight: Option<&Highlight>, x: i32, y: i32) -> bool {
+        return highlight.map(|h| h.cells.contains(&(x, y))).unwrap_or(false)
+    }
+}
+
+// Colours are interpolated linearly between the stops, from the lowest value to the highest
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Palette {
+    stops: Vec<Rgb>
+}
+
+impl Palette {
+    pub fn new(stops: Vec<Rgb>) -> Palette {
+        if stops.is_empty() { panic!("Palette needs at least one colour") }
+        return Palette { stops }
+    }
+
+    pub fn grayscale() -> Palette {
+        return Palette::new(vec![(0, 0, 0), (255, 255, 255)])
+    }
+
+    pub fn heat() -> Palette {
+        return Palette::new(vec![(0, 0, 128), (0, 160, 255), (255, 255, 0), (255, 64, 0)])
+    }
+
+    pub fn color_at(&self, fraction: f64) -> Rgb {
+        let fraction = fraction.clamp(0.0, 1.0);
+        if self.stops.len() == 1 { return self.stops[0] }
+        let position = fraction * (self.stops.len() - 1) as f64;
+        let idx = (position.floor() as usize).min(self.stops.len() - 2);
+        let t = position - idx as f64;
+        let (from, to) = (self.stops[idx], self.stops[idx + 1]);
+        let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+        return (mix(from.0, to.0), mix(from.1, to.1), mix(from.2, to.2))
+    }
+}
+
+impl<T: Clone + fmt::Display> fmt::Display for Matrix<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for y in 0..self.n_rows() {
+            if y > 0 { writeln!(f)? }
+            for value in self.row(y) {
+                write!(f, "{}", value)?
+            }
+        }
+        return Ok(())
+    }
+}
+
+impl<T: Clone> Matrix<T> {
+    pub fn render_chars<F>(&self, to_char: F, highlight: Option<&Highlight>) -> Vec<String>
+        where F: Fn(&T) -> char {
+        return (0..self.n_rows()).map(|y|
+            self.row(y).iter().enumerate().map(|(x, value)|
+                if Highlight::contains(highlight, x as i32, y as i32) { highlight.unwrap().marker }
+                else { to_char(value) }
+            ).collect()
+        ).collect()
+    }
+
+    // Every cell is prefixed by the highlight marker or a space, values are right-aligned
+    pub fn render_aligned(&self, separator: &str, highlight: Option<&Highlight>) -> Vec<String>
+        where T: fmt::Display {
+        let width = self.points_iter().map(|v| v.to_string().len()).max().unwrap_or(0);
+        return (0..self.n_rows()).map(|y|
+            self.row(y).iter().enumerate().map(|(x, value)| {
+                let marker = if Highlight::contains(highlight, x as i32, y as i32) {
+                    highlight.unwrap().marker
+                } else { ' ' };
+                format!("{}{:>width$}", marker, value, width = width)
+            }).collect::<Vec<String>>().join(separator)
+        ).collect()
+    }
+
+    pub fn render_heatmap(&self, palette: &Palette, highlight: Option<&Highlight>) -> Vec<String>
+        where T: HeatValue + fmt::Display {
+        let range = self._value_range();
+        return (0..self.n_rows()).map(|y|
+            (0..self.n_columns()).map(|x| {
+                let value = self.row(y)[x];
+                let (r, g, b) = self._cell_color(x, y, range, palette, highlight);
+                format!("\x1b[48;2;{};{};{}m{}\x1b[0m", r, g, b, value)
+            }).collect::<String>()
+        ).collect()
+    }
+
+    pub fn to_ppm(&self, palette: &Palette, highlight: Option<&Highlight>, scale: usize) -> Vec<u8>
+        where T: HeatValue {
+        let (width, height) = (self.n_columns() * scale, self.n_rows() * scale);
+        let range = self._value_range();
+        let mut image = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+        for y in 0..height {
+            for x in 0..width {
+                let (r, g, b) = self._cell_color(x / scale, y / scale, range, palette, highlight);
+                image.extend_from_slice(&[r, g, b]);
+            }
+        }
+        return image
+    }
+
+    pub fn write_ppm(
+        &self,
+        file_path: &str,
+        palette: &Palette,
+        highlight: Option<&Highlight>,
+        scale: usize
+    ) -> std::io::Result<()> where T: HeatValue {
+        let mut file = File::create(file_path)?;
+        return file.write_all(&self.to_ppm(palette, highlight, scale))
+    }
+
+    // Every cell becomes a node pinned at its position (render with `neato -n`),
+    // consecutive path cells are linked by arrows
+    pub fn path_to_dot(&self, palette: &Palette, path: &[(i32, i32)]) -> String
+        where T: HeatValue + fmt::Display {
+        let range = self._value_range();
+        let on_path: HashSet<(i32, i32)> = path.iter().cloned().collect();
+        let mut dot = String::from("digraph grid {\n");
+        dot.push_str("    node [shape=square, style=filled, fixedsize=true, width=0.5];\n");
+        for y in 0..self.n_rows() {
+            for x in 0..self.n_columns() {
+                let border = if on_path.contains(&(x as i32, y as i32)) {
+                    ", color=\"#ff0000\", penwidth=3"
+                } else { "" };
+                dot.push_str(&format!(
+                    "    {} [label=\"{}\", pos=\"{},{}\", fillcolor=\"{}\"{}];\n",
+                    dot_id(&format!("{},{}", x, y)), self.row(y)[x], x * 36, -(y as i64) * 36,
+                    hex_color(self._cell_color(x, y, range, palette, None)), border
+                ));
+            }
+        }
+        for step in path.windows(2) {
+            dot.push_str(&format!(
+                "    {} -> {} [color=\"#ff0000\", penwidth=2];\n",
+                dot_id(&format!("{},{}", step[0].0, step[0].1)),
+                dot_id(&format!("{},{}", step[1].0, step[1].1))
+            ));
+        }
+        dot.push_str("}\n");
+        return dot
+    }
+
+    // Cells are drawn as squares of `cell_size` pixels, the path as a line through their centres
+    pub fn path_to_svg(&self, palette: &Palette, path: &[(i32, i32)], cell_size: usize) -> String
+        where T: HeatValue {
+        let range = self._value_range();
+        let (width, height) = (self.n_columns() * cell_size, self.n_rows() * cell_size);
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n",
+            w = width, h = height
+        );
+        for y in 0..self.n_rows() {
+            for x in 0..self.n_columns() {
+                svg.push_str(&format!(
+                    "<rect x=\"{}\" y=\"{}\" width=\"{s}\" height=\"{s}\" fill=\"{}\"/>\n",
+                    x * cell_size, y * cell_size,
+                    hex_color(self._cell_color(x, y, range, palette, None)), s = cell_size
+                ));
+            }
+        }
+        if !path.is_empty() {
+            let centre = |c: i32| c as f64 * cell_size as f64 + cell_size as f64 / 2.0;
+            let points = path.iter()
+                .map(|(x, y)| format!("{},{}", centre(*x), centre(*y)))
+                .collect::<Vec<String>>().join(" ");
+            svg.push_str(&format!(
+                "<polyline points=\"{}\" fill=\"none\" stroke=\"#ff0000\" stroke-width=\"{}\"/>\n",
+                points, (cell_size as f64 / 4.0).max(1.0)
+            ));
+        }
+        svg.push_str("</svg>\n");
+        return svg
+    }
+
+    fn _value_range(&self) -> (f64, f64) where T: HeatValue {
+        return self.points_iter().map(|v| v.heat())
+            .fold((f64::MAX, f64::MIN), |(lo, hi), v: f64| (lo.min(v), hi.max(v)))
+    }
+
+    fn _cell_color(
+        &self,
+        x: usize,
+        y: usize,
+        (low, high): (f64, f64),
+        palette: &Palette,
+        highlight: Option<&Highlight>
+    ) -> Rgb where T: HeatValue {
+        if Highlight::contains(highlight, x as i32, y as i32) { return highlight.unwrap().color }
+        let value = self.row(y)[x].heat();
+        let fraction = if high > low { (value - low) / (high - low) } else { 0.0 };
+        return palette.color_at(fraction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+    use super::*;
+    use crate::exs::utils::strs_to_strings;
+
+    fn sample() -> Matrix<u32> {
+        return Matrix::<u32>::from_lines(&strs_to_strings(&[
+            "190",
+            "025"
+        ]))
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(sample().to_string(), "190\n025");
+    }
+
+    #[test]
+    fn test_render_chars() {
+        let m = sample();
+        assert_eq!(
+            m.render_chars(|v| if *v == 0 { '.' } else { '#' }, None),
+            strs_to_strings(&["##.", ".##"])
+        );
+        let path = Highlight::new(vec![(0, 0), (1, 1)]).with_marker('o');
+        assert_eq!(
+            m.render_chars(|v| if *v == 0 { '.' } else { '#' }, Some(&path)),
+            strs_to_strings(&["o#.", ".o#"])
+        );
+    }
+
+    #[test]
+    fn test_render_aligned() {
+        let m = Matrix::from_rows(vec![vec![1u32, 10, 100], vec![7, 0, 42]]);
+        assert_eq!(
+            m.render_aligned(",", None),
+            strs_to_strings(&["   1,  10, 100", "   7,   0,  42"])
+        );
+        let flashed = Highlight::new(vec![(1, 1)]);
+        assert_eq!(
+            m.render_aligned(" ", Some(&flashed)),
+            strs_to_strings(&["   1   10  100", "   7 *  0   42"])
+        );
+    }
+
+    #[test]
+    fn test_render_heatmap() {
+        let m = Matrix::from_rows(vec![vec![0u32, 5]]);
+        let palette = Palette::grayscale();
+        assert_eq!(
+            m.render_heatmap(&palette, None),
+            vec!["\x1b[48;2;0;0;0m0\x1b[0m\x1b[48;2;255;255;255m5\x1b[0m".to_string()]
+        );
+        let basin = Highlight::new(vec![(1, 0)]).with_color((0, 255, 0));
+        assert_eq!(
+            m.render_heatmap(&palette, Some(&basin)),
+            vec!["\x1b[48;2;0;0;0m0\x1b[0m\x1b[48;2;0;255;0m5\x1b[0m".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_wide_heat_values() {
+        let palette = Palette::grayscale();
+        let wide = Matrix::from_rows(vec![vec![0u64, u64::MAX]]);
+        let signed = Matrix::from_rows(vec![vec![i64::MIN, i64::MAX]]);
+        assert_eq!(wide.to_ppm(&palette, None, 1)[11..], [0, 0, 0, 255, 255, 255]);
+        assert_eq!(signed.to_ppm(&palette, None, 1), wide.to_ppm(&palette, None, 1));
+    }
+
+    #[test]
+    fn test_to_ppm() {
+        let m = Matrix::from_rows(vec![vec![0u32, 10], vec![5, 10]]);
+        let ppm = m.to_ppm(&Palette::grayscale(), Some(&Highlight::new(vec![(1, 1)])), 1);
+        let header = b"P6\n2 2\n255\n";
+        assert_eq!(&ppm[..header.len()], header);
+        assert_eq!(
+            &ppm[header.len()..],
+            &[0, 0, 0, 255, 255, 255, 128, 128, 128, 255, 0, 0]
+        );
+        assert_eq!(m.to_ppm(&Palette::heat(), None, 3).len(), header.len() + 6 * 6 * 3);
+
+        let path = env::temp_dir().join(format!("render-test-{}.ppm", std::process::id()));
+        let path = path.to_str().unwrap();
+        m.write_ppm(path, &Palette::grayscale(), None, 2).unwrap();
+        assert_eq!(fs::read(path).unwrap(), m.to_ppm(&Palette::grayscale(), None, 2));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_palette() {
+        let palette = Palette::new(vec![(0, 0, 0), (100, 200, 0), (200, 200, 200)]);
+        assert_eq!(palette.color_at(0.0), (0, 0, 0));
+        assert_eq!(palette.color_at(0.25), (50, 100, 0));
+        assert_eq!(palette.color_at(0.5), (100, 200, 0));
+        assert_eq!(palette.color_at(1.0), (200, 200, 200));
+        assert_eq!(palette.color_at(7.0), (200, 200, 200));
+    }
+
+    #[test]
+    fn test_path_to_dot() {
+        let m = Matrix::from_rows(vec![vec![0u32, 10], vec![5, 10]]);
+        assert_eq!(
+            m.path_to_dot(&Palette::grayscale(), &[(0, 0), (1, 0)]),
+            "digraph grid {\n\
+            \x20   node [shape=square, style=filled, fixedsize=true, width=0.5];\n\
+            \x20   \"0,0\" [label=\"0\", pos=\"0,0\", fillcolor=\"#000000\", color=\"#ff0000\", penwidth=3];\n\
+            \x20   \"1,0\" [label=\"10\", pos=\"36,0\", fillcolor=\"#ffffff\", color=\"#ff0000\", penwidth=3];\n\
+            \x20   \"0,1\" [label=\"5\", pos=\"0,-36\", fillcolor=\"#808080\"];\n\
+            \x20   \"1,1\" [label=\"10\", pos=\"36,-36\", fillcolor=\"#ffffff\"];\n\
+            \x20   \"0,0\" -> \"1,0\" [color=\"#ff0000\", penwidth=2];\n\
+            }\n"
+        );
+    }
+
+    #[test]
+    fn test_path_to_svg() {
+        let m = Matrix::from_rows(vec![vec![0u32, 10]]);
+        assert_eq!(
+            m.path_to_svg(&Palette::grayscale(), &[(0, 0), (1, 0)], 8),
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"16\" height=\"8\" viewBox=\"0 0 16 8\">\n\
+            <rect x=\"0\" y=\"0\" width=\"8\" height=\"8\" fill=\"#000000\"/>\n\
+            <rect x=\"8\" y=\"0\" width=\"8\" height=\"8\" fill=\"#ffffff\"/>\n\
+            <polyline points=\"4,4 12,4\" fill=\"none\" stroke=\"#ff0000\" stroke-width=\"2\"/>\n\
+            </svg>\n"
+        );
+    }
+
+    #[test]
+    fn test_dot_id() {
+        assert_eq!(dot_id("start"), "\"start\"");
+        assert_eq!(dot_id("a\"b"), "\"a\\\"b\"");
+    }
+}
@@ -0,0 +1,278 @@
+use std::str::FromStr;
+use super::utils::{json_array, read_lines_as_vector};
+use super::window::{rolling, Aggregate};
+
+// One line of a depth report, blank lines and "?", "-" or "NA" mark missing readings
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Reading(pub Option<i32>);
+
+impl FromStr for Reading {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Reading, String> {
+        return match s.trim() {
+            "" | "?" | "-" | "NA" => Ok(Reading(None)),
+            depth => depth.parse::<i32>().map(|d| Reading(Some(d)))
+                .map_err(|_| format!("{:?} is neither a depth nor a missing reading", depth))
+        }
+    }
+}
+
+// Missing readings are interpolated linearly between the closest known ones,
+// before the first and after the last known reading they repeat it
+pub fn fill_gaps(readings: &[Reading]) -> Result<Vec<i32>, String> {
+    let known: Vec<(usize, i32)> = readings.iter().enumerate()
+        .filter_map(|(idx, reading)| reading.0.map(|depth| (idx, depth)))
+        .collect();
+    if known.is_empty() {
+        return Err("There are no readings".to_string())
+    }
+    let mut next_known: usize = 0;
+    return Ok(readings.iter().enumerate().map(|(idx, reading)| {
+        if let Some(depth) = reading.0 {
+            next_known += 1;
+            return depth
+        }
+        match (next_known.checked_sub(1).map(|k| known[k]), known.get(next_known)) {
+            (Some((from, a)), Some((to, b))) => {
+                let fraction = (idx - from) as f64 / (to - from) as f64;
+                (a as f64 + (b - a) as f64 * fraction).round() as i32
+            },
+            (Some((_, a)), None) => a,
+            (None, Some((_, b))) => *b,
+            (None, None) => unreachable!()
+        }
+    }).collect())
+}
+
+// Linear interpolation between the closest ranks, `sorted` has to be non-empty
+pub fn percentile(sorted: &[i32], p: f64) -> f64 {
+    let rank = p.clamp(0.0, 100.0) / 100.0 * (sorted.len() - 1) as f64;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    return sorted[lower] as f64 + (sorted[upper] - sorted[lower]) as f64 * (rank - lower as f64)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Outlier {
+    pub index: usize,
+    pub depth: i32,
+    pub median: f64
+}
+
+// Readings further than `threshold` from the median of the `window` readings
+// centred on them. Windows are shifted inwards at both ends of the series.
+pub fn outliers(depths: &[i32], window: usize, threshold: f64) -> Vec<Outlier> {
+    let window = window.min(depths.len());
+    if window == 0 {
+        return Vec::new()
+    }
+    let medians: Vec<f64> = rolling(depths.iter().copied(), window, Aggregate::Median).collect();
+    return depths.iter().enumerate().filter_map(|(index, depth)| {
+        let median = medians[index.saturating_sub(window / 2).min(medians.len() - 1)];
+        if (*depth as f64 - median).abs() > threshold {
+            Some(Outlier { index, depth: *depth, median })
+        } else {
+            None
+        }
+    }).collect()
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Plateau {
+    pub start: usize,
+    pub length: usize,
+    pub depth: i32
+}
+
+// Runs of at least `min_length` equal readings
+pub fn plateaus(depths: &[i32], min_length: usize) -> Vec<Plateau> {
+    let mut found = Vec::new();
+    let mut start = 0;
+    for idx in 1..=depths.len() {
+        if idx == depths.len() || depths[idx] != depths[start] {
+            if idx - start >= min_length.max(1) {
+                found.push(Plateau { start, length: idx - start, depth: depths[start] })
+            }
+            start = idx;
+        }
+    }
+    return found
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SonarOptions {
+    pub outlier_window: usize,
+    pub outlier_threshold: f64,
+    pub min_plateau: usize
+}
+
+impl Default for SonarOptions {
+    fn default() -> SonarOptions {
+        return SonarOptions { outlier_window: 5, outlier_threshold: 20.0, min_plateau: 3 }
+    }
+}
+
+impl SonarOptions {
+    pub fn with_outliers(mut self, window: usize, threshold: f64) -> SonarOptions {
+        self.outlier_window = window;
+        self.outlier_threshold = threshold;
+        return self
+    }
+
+    pub fn with_min_plateau(mut self, min_plateau: usize) -> SonarOptions {
+        self.min_plateau = min_plateau;
+        return self
+    }
+}
+
+pub const PERCENTILES: [u32; 5] = [10, 25, 50, 75, 90];
+
+// Statistics over the gap-filled series, `missing` counts the readings filled in
+#[derive(Clone, Debug, PartialEq)]
+pub struct SonarReport {
+    pub count: usize,
+    pub missing: usize,
+    pub min: i32,
+    pub max: i32,
+    pub mean: f64,
+    pub stddev: f64,
+    pub percentiles: Vec<(u32, f64)>,
+    pub outliers: Vec<Outlier>,
+    pub plateaus: Vec<Plateau>
+}
+
+impl SonarReport {
+    pub fn new(readings: &[Reading], options: &SonarOptions) -> Result<SonarReport, String> {
+        let depths = fill_gaps(readings)?;
+        let mut sorted = depths.clone();
+        sorted.sort_unstable();
+        let count = depths.len();
+        let mean = depths.iter().map(|d| *d as f64).sum::<f64>() / count as f64;
+        let variance = depths.iter().map(|d| (*d as f64 - mean).powi(2)).sum::<f64>() / count as f64;
+        return Ok(SonarReport {
+            count,
+            missing: readings.iter().filter(|r| r.0.is_none()).count(),
+            min: sorted[0],
+            max: sorted[count - 1],
+            mean,
+            stddev: variance.sqrt(),
+            percentiles: PERCENTILES.iter().map(|p| (*p, percentile(&sorted, *p as f64))).collect(),
+            outliers: outliers(&depths, options.outlier_window, options.outlier_threshold),
+            plateaus: plateaus(&depths, options.min_plateau)
+        })
+    }
+
+    pub fn repr(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("Readings: {} ({} missing, filled in)", self.count, self.missing),
+            format!("Min: {}", self.min),
+            format!("Max: {}", self.max),
+            format!("Mean: {:.2}", self.mean),
+            format!("Stddev: {:.2}", self.stddev)
+        ];
+        lines.extend(self.percentiles.iter().map(|(p, value)| format!("P{}: {:.1}", p, value)));
+        lines.push(format!("Outliers: {}", self.outliers.len()));
+        lines.extend(self.outliers.iter().map(|o| {
+            format!("  #{}: {} (rolling median {:.1})", o.index, o.depth, o.median)
+        }));
+        lines.push(format!("Plateaus: {}", self.plateaus.len()));
+        lines.extend(self.plateaus.iter().map(|p| {
+            format!("  #{}..#{}: {}", p.start, p.start + p.length - 1, p.depth)
+        }));
+        return lines
+    }
+
+    // Written out by hand so that the command needs no serde
+    pub fn to_json(&self) -> String {
+        let percentiles = self.percentiles.iter()
+            .map(|(p, value)| format!("\"p{}\": {}", p, value))
+            .collect::<Vec<String>>().join(", ");
+        let outliers = json_array(self.outliers.iter()
+            .map(|o| format!("{{\"index\": {}, \"depth\": {}, \"median\": {}}}", o.index, o.depth, o.median)));
+        let plateaus = json_array(self.plateaus.iter()
+            .map(|p| format!("{{\"start\": {}, \"length\": {}, \"depth\": {}}}", p.start, p.length, p.depth)));
+        return format!(
+            "{{\"count\": {}, \"missing\": {}, \"min\": {}, \"max\": {}, \"mean\": {}, \"stddev\": {}, \
+             \"percentiles\": {{{}}}, \"outliers\": {}, \"plateaus\": {}}}",
+            self.count, self.missing, self.min, self.max, self.mean, self.stddev, percentiles, outliers, plateaus
+        )
+    }
+}
+
+// Usage: sonar-stats <input-file> [text|json] [outlier-window] [outlier-threshold] [min-plateau]
+pub fn sonar_stats_command(args: &[String]) {
+    let usage = "Usage: sonar-stats <input-file> [text|json] [outlier-window] [outlier-threshold] [min-plateau]";
+    let filename = match args.first() {
+        Some(filename) => filename,
+        None => panic!("{}", usage)
+    };
+    let defaults = SonarOptions::default();
+    let window = args.get(2).map(|w| w.parse::<usize>().expect(usage)).unwrap_or(defaults.outlier_window);
+    let threshold = args.get(3).map(|t| t.parse::<f64>().expect(usage)).unwrap_or(defaults.outlier_threshold);
+    let min_plateau = args.get(4).map(|m| m.parse::<usize>().expect(usage)).unwrap_or(defaults.min_plateau);
+    if window == 0 || min_plateau < 2 {
+        panic!("{}", usage)
+    }
+    let options = defaults.with_outliers(window, threshold).with_min_plateau(min_plateau);
+
+    let readings = read_lines_as_vector::<Reading>(filename);
+    let report = SonarReport::new(&readings, &options)
+        .unwrap_or_else(|e| panic!("Invalid depth report in {}: {}", filename, e));
+    match args.get(1).map(|f| f.as_str()).unwrap_or("text") {
+        "text" => report.repr().iter().for_each(|line| println!("{}", line)),
+        "json" => println!("{}", report.to_json()),
+        format => panic!("Unknown output format {:?}, expected text or json", format)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn readings(encoded: &[&str]) -> Vec<Reading> {
+        return encoded.iter().map(|r| r.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_parse_and_fill_gaps() {
+        assert!("12a".parse::<Reading>().is_err());
+        let series = readings(&["?", "100", "", "NA", "106", "107", "-"]);
+        assert_eq!(series[1], Reading(Some(100)));
+        assert_eq!(fill_gaps(&series), Ok(vec![100, 100, 102, 104, 106, 107, 107]));
+        assert!(fill_gaps(&readings(&["?", ""])).is_err());
+    }
+
+    #[test]
+    fn test_percentiles_outliers_and_plateaus() {
+        let sorted = [1, 2, 3, 4, 10];
+        assert_eq!(percentile(&sorted, 50.0), 3.0);
+        assert_eq!(percentile(&sorted, 62.5), 3.5);
+        assert_eq!(percentile(&sorted, 100.0), 10.0);
+
+        let depths = [200, 201, 202, 260, 203, 204, 204, 204, 150, 205];
+        assert_eq!(outliers(&depths, 3, 20.0), vec![
+            Outlier { index: 3, depth: 260, median: 203.0 },
+            Outlier { index: 8, depth: 150, median: 204.0 }
+        ]);
+        assert_eq!(outliers(&depths[..2], 5, 0.4), vec![
+            Outlier { index: 0, depth: 200, median: 200.5 },
+            Outlier { index: 1, depth: 201, median: 200.5 }
+        ]);
+        assert_eq!(plateaus(&depths, 3), vec![Plateau { start: 5, length: 3, depth: 204 }]);
+        assert_eq!(plateaus(&[7, 7], 2), vec![Plateau { start: 0, length: 2, depth: 7 }]);
+    }
+
+    #[test]
+    fn test_report() {
+        let series = readings(&["199", "200", "?", "210", "200", "200", "200", "269", "260", "263"]);
+        let report = SonarReport::new(&series, &SonarOptions::default().with_outliers(5, 5.0)).unwrap();
+        assert_eq!((report.count, report.missing, report.min, report.max), (10, 1, 199, 269));
+        assert_eq!(report.mean, 220.6);
+        assert_eq!(report.percentiles[2], (50, 202.5));
+        assert_eq!(report.plateaus, vec![Plateau { start: 4, length: 3, depth: 200 }]);
+        assert_eq!(report.outliers.iter().map(|o| o.index).collect::<Vec<usize>>(), vec![3, 7]);
+        assert_eq!(report.repr()[0], "Readings: 10 (1 missing, filled in)");
+        assert!(report.to_json().starts_with("{\"count\": 10, \"missing\": 1, \"min\": 199, \"max\": 269, \"mean\": 220.6, "));
+        assert!(report.to_json().contains("\"plateaus\": [{\"start\": 4, \"length\": 3, \"depth\": 200}]"));
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+use super::matrix::Matrix;
+
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug)]
+pub struct BoundingBox {
+    pub min_x: i32,
+    pub min_y: i32,
+    pub max_x: i32,
+    pub max_y: i32
+}
+
+impl BoundingBox {
+    pub fn width(&self) -> usize {
+        return (self.max_x - self.min_x + 1) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        return (self.max_y - self.min_y + 1) as usize
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        return x >= self.min_x && x <= self.max_x && y >= self.min_y && y <= self.max_y
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct SparseGrid<T> {
+    cells: HashMap<(i32, i32), T>
+}
+
+impl<T: Clone> Default for SparseGrid<T> {
+    fn default() -> SparseGrid<T> {
+        return SparseGrid::new()
+    }
+}
+
+impl<T: Clone> SparseGrid<T> {
+    pub fn new() -> SparseGrid<T> {
+        return SparseGrid { cells: HashMap::new() }
+    }
+
+    pub fn insert(&mut self, x: i32, y: i32, value: T) -> Option<T> {
+        return self.cells.insert((x, y), value)
+    }
+
+    pub fn remove(&mut self, x: i32, y: i32) -> Option<T> {
+        return self.cells.remove(&(x, y))
+    }
+
+    pub fn get(&self, x: i32, y: i32) -> Option<&T> {
+        return self.cells.get(&(x, y))
+    }
+
+    pub fn get_mut(&mut self, x: i32, y: i32) -> Option<&mut T> {
+        return self.cells.get_mut(&(x, y))
+    }
+
+    pub fn entry_or_insert(&mut self, x: i32, y: i32, default: T) -> &mut T {
+        return self.cells.entry((x, y)).or_insert(default)
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        return self.cells.contains_key(&(x, y))
+    }
+
+    pub fn len(&self) -> usize {
+        return self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.cells.is_empty()
+    }
+
+    pub fn values(&self) -> impl Iterator<Item=&T> + '_ {
+        return self.cells.values()
+    }
+
+    pub fn bounding_box(&self) -> Option<BoundingBox> {
+        let mut keys = self.cells.keys();
+        let &(first_x, first_y) = keys.next()?;
+        let mut bbox = BoundingBox { min_x: first_x, min_y: first_y, max_x: first_x, max_y: first_y };
+        for &(x, y) in keys {
+            bbox.min_x = bbox.min_x.min(x);
+            bbox.min_y = bbox.min_y.min(y);
+            bbox.max_x = bbox.max_x.max(x);
+            bbox.max_y = bbox.max_y.max(y);
+        }
+        return Some(bbox)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item=((i32, i32), &T)> + '_ {
+        let mut coordinates: Vec<&(i32, i32)> = self.cells.keys().collect();
+        coordinates.sort_by_key(|(x, y)| (*y, *x));
+        return coordinates.into_iter()
+            .map(move |coordinate| (*coordinate, self.cells.get(coordinate).unwrap()))
+    }
+
+    pub fn from_matrix(matrix: &Matrix<T>, default: &T) -> SparseGrid<T> where T: PartialEq {
+        let mut grid = SparseGrid::new();
+        for y in 0..(matrix.n_rows() as i32) {
+            for x in 0..(matrix.n_columns() as i32) {
+                let value = matrix.get_point(x, y).unwrap();
+                if &value != default { grid.insert(x, y, value); }
+            }
+        }
+        return grid
+    }
+
+    // The matrix covers the bounding box: cell (0, 0) corresponds to (min_x, min_y)
+    pub fn to_matrix(&self, default: T) -> Option<(Matrix<T>, BoundingBox)> {
+        let bbox = self.bounding_box()?;
+        let mut matrix = Matrix::filled(bbox.height(), bbox.width(), default);
+        for (&(x, y), value) in &self.cells {
+            let cell = matrix.get_mut_point(x - bbox.min_x, y - bbox.min_y).unwrap();
+            *cell = value.clone();
+        }
+        return Some((matrix, bbox))
+    }
+
+    pub fn render<F>(&self, empty: char, to_char: F) -> Vec<String> where F: Fn(&T) -> char {
+        return match self.bounding_box() {
+            Some(bbox) => self.render_within(&bbox, empty, to_char),
+            None => Vec::new()
+        }
+    }
+
+    pub fn render_within<F>(&self, bbox: &BoundingBox, empty: char, to_char: F) -> Vec<String>
+        where F: Fn(&T) -> char {
+        return (bbox.min_y..=bbox.max_y).map(|y|
+            (bbox.min_x..=bbox.max_x).map(|x|
+                match self.get(x, y) {
+                    Some(value) => to_char(value),
+                    None => empty
+                }
+            ).collect::<String>()
+        ).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exs::utils::strs_to_strings;
+
+    #[test]
+    fn test_insert_remove() {
+        let mut grid = SparseGrid::new();
+        assert!(grid.is_empty());
+        assert_eq!(grid.bounding_box(), None);
+        assert_eq!(grid.insert(-3, 2, 'a'), None);
+        assert_eq!(grid.insert(-3, 2, 'b'), Some('a'));
+        grid.insert(5, -1, 'c');
+        assert_eq!(grid.len(), 2);
+        assert_eq!(grid.get(-3, 2), Some(&'b'));
+        *grid.get_mut(-3, 2).unwrap() = 'd';
+        assert_eq!(grid.get(-3, 2), Some(&'d'));
+        assert!(!grid.contains(0, 0));
+        let bbox = grid.bounding_box().unwrap();
+        assert_eq!(bbox, BoundingBox { min_x: -3, min_y: -1, max_x: 5, max_y: 2 });
+        assert!(bbox.contains(0, 0) && !bbox.contains(6, 0));
+        assert_eq!(grid.remove(5, -1), Some('c'));
+        assert_eq!(grid.remove(5, -1), None);
+        assert_eq!(
+            grid.bounding_box(),
+            Some(BoundingBox { min_x: -3, min_y: 2, max_x: -3, max_y: 2 })
+        );
+    }
+
+    #[test]
+    fn test_iter_is_row_major() {
+        let mut grid = SparseGrid::new();
+        grid.insert(1, 1, 4);
+        grid.insert(0, 1, 3);
+        grid.insert(-1, -1, 1);
+        grid.insert(2, -1, 2);
+        let visited: Vec<((i32, i32), i32)> = grid.iter().map(|(c, v)| (c, *v)).collect();
+        assert_eq!(visited, vec![((-1, -1), 1), ((2, -1), 2), ((0, 1), 3), ((1, 1), 4)]);
+    }
+
+    #[test]
+    fn test_matrix_round_trip() {
+        let m = Matrix::<u32>::from_lines(&strs_to_strings(&vec![
+            "100",
+            "020",
+            "003"
+        ]));
+        let grid = SparseGrid::from_matrix(&m, &0);
+        assert_eq!(grid.len(), 3);
+        assert_eq!(grid.get(1, 1), Some(&2));
+        let (dense, bbox) = grid.to_matrix(0).unwrap();
+        assert_eq!(dense, m);
+        assert_eq!(bbox, BoundingBox { min_x: 0, min_y: 0, max_x: 2, max_y: 2 });
+
+        let mut shifted = SparseGrid::new();
+        shifted.insert(-2, -2, 7u32);
+        shifted.insert(-1, -2, 8u32);
+        let (dense, _) = shifted.to_matrix(0).unwrap();
+        assert_eq!(dense, Matrix::<u32>::from_lines(&strs_to_strings(&vec!["78"])));
+    }
+
+    #[test]
+    fn test_render() {
+        let mut grid = SparseGrid::new();
+        grid.insert(-1, 0, true);
+        grid.insert(1, 1, true);
+        assert_eq!(grid.render('.', |_| '#'), strs_to_strings(&vec!["#..", "..#"]));
+        assert_eq!(SparseGrid::<bool>::new().render('.', |_| '#'), Vec::<String>::new());
+    }
+}
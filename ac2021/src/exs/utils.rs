@@ -41,11 +41,6 @@ pub fn lines_reader(file_path: &str) -> Map<Lines<BufReader<File>>, fn(std::io::
         .map(|line| line.expect("Could not parse line"))
 }
 
-pub fn open_range_vec(start: u32, end: u32) -> Vec<u32> {
-    if end >= start { (start..=end).collect::<Vec<u32>>() }
-    else { (end..=start).rev().collect::<Vec<u32>>() }
-}
-
 pub fn strs_to_strings(strs: &[&str]) -> Vec<String> {
     return strs.iter().map(|s| s.to_string()).collect()
 }
@@ -67,6 +62,28 @@ pub fn frequencies<T: Eq + Hash + Clone>(input: &[T]) -> HashMap<T, u64> {
     return output
 }
 
+// Quoted JSON string, for the reports that write their JSON by hand
+pub fn json_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c)
+        }
+    }
+    quoted.push('"');
+    return quoted
+}
+
+pub fn json_array<I>(items: I) -> String where I: Iterator<Item=String> {
+    return format!("[{}]", items.collect::<Vec<String>>().join(", "))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -85,4 +102,12 @@ mod tests {
         assert_eq!(frequencies(&vec![7, 3]), hashmap![7 => 1, 3 => 1]);
         assert_eq!(frequencies(&vec![7, 3, 3]), hashmap![7 => 1, 3 => 2]);
     }
+
+    #[test]
+    fn test_json_helpers() {
+        assert_eq!(json_string("kc"), "\"kc\"");
+        assert_eq!(json_string("a\"b\\c\nd\u{1}"), "\"a\\\"b\\\\c\\nd\\u0001\"");
+        assert_eq!(json_array(Vec::<String>::new().into_iter()), "[]");
+        assert_eq!(json_array(["A", "b"].iter().map(|c| json_string(c))), "[\"A\", \"b\"]");
+    }
 }
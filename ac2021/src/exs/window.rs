@@ -0,0 +1,283 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Aggregate {
+    Sum,
+    Mean,
+    Min,
+    Max,
+    Median
+}
+
+impl Aggregate {
+    pub fn parse(name: &str) -> Result<Aggregate, String> {
+        return match name {
+            "sum" => Ok(Aggregate::Sum),
+            "mean" => Ok(Aggregate::Mean),
+            "min" => Ok(Aggregate::Min),
+            "max" => Ok(Aggregate::Max),
+            "median" => Ok(Aggregate::Median),
+            _ => Err(format!("Unknown aggregate {:?}, expected sum, mean, min, max or median", name))
+        }
+    }
+}
+
+// The lower half of the window in a max-heap and the upper half in a min-heap,
+// as (value, position) so that equal values stay apart. Values leaving the window
+// are only taken out of the counts, and popped once they surface at the top.
+struct MedianHeaps {
+    low: BinaryHeap<(i64, usize)>,
+    high: BinaryHeap<Reverse<(i64, usize)>>,
+    n_low: usize,
+    n_high: usize
+}
+
+impl MedianHeaps {
+    fn new() -> MedianHeaps {
+        return MedianHeaps { low: BinaryHeap::new(), high: BinaryHeap::new(), n_low: 0, n_high: 0 }
+    }
+
+    fn insert(&mut self, entry: (i64, usize), first: usize) {
+        if self.low.peek().map(|top| entry <= *top).unwrap_or(true) {
+            self.low.push(entry);
+            self.n_low += 1;
+        } else {
+            self.high.push(Reverse(entry));
+            self.n_high += 1;
+        }
+        self._rebalance(first)
+    }
+
+    // `entry` has to be in the window still, `first` is the oldest position kept
+    fn remove(&mut self, entry: (i64, usize), first: usize) {
+        if self.low.peek().map(|top| entry <= *top).unwrap_or(false) {
+            self.n_low -= 1;
+        } else {
+            self.n_high -= 1;
+        }
+        self._rebalance(first)
+    }
+
+    // Keeps the low half as large as the high one or one larger, with live tops
+    fn _rebalance(&mut self, first: usize) {
+        self._prune(first);
+        if self.n_low > self.n_high + 1 {
+            let top = self.low.pop().unwrap();
+            self.high.push(Reverse(top));
+            self.n_low -= 1;
+            self.n_high += 1;
+        } else if self.n_low < self.n_high {
+            let Reverse(top) = self.high.pop().unwrap();
+            self.low.push(top);
+            self.n_high -= 1;
+            self.n_low += 1;
+        }
+        self._prune(first)
+    }
+
+    fn _prune(&mut self, first: usize) {
+        // Expired values below the tops only surface when the window moves back
+        // over them, so the heaps are rebuilt once those make up half of them
+        if self.low.len() + self.high.len() > 2 * (self.n_low + self.n_high) + 2 {
+            self.low = self.low.drain().filter(|(_, pos)| *pos >= first).collect();
+            self.high = self.high.drain().filter(|Reverse((_, pos))| *pos >= first).collect();
+        }
+        while self.low.peek().map(|(_, pos)| *pos < first).unwrap_or(false) {
+            self.low.pop();
+        }
+        while self.high.peek().map(|Reverse((_, pos))| *pos < first).unwrap_or(false) {
+            self.high.pop();
+        }
+    }
+
+    fn median(&self) -> f64 {
+        let (lower, _) = self.low.peek().unwrap();
+        if self.n_low > self.n_high {
+            return *lower as f64
+        }
+        let Reverse((upper, _)) = self.high.peek().unwrap();
+        return (lower + upper) as f64 / 2.0
+    }
+}
+
+// Aggregate of the last `size` values pushed, updated as every value comes in:
+// a running total for the sum and mean, monotonic deques of (position, value)
+// for the min and max and two heaps for the median. The sum, mean, min and max
+// take amortised O(1) per value, the median O(log size).
+pub struct RollingWindow {
+    size: usize,
+    aggregate: Aggregate,
+    values: VecDeque<i64>,
+    pushed: usize,
+    sum: i64,
+    extremes: VecDeque<(usize, i64)>,
+    halves: MedianHeaps
+}
+
+impl RollingWindow {
+    pub fn new(size: usize, aggregate: Aggregate) -> RollingWindow {
+        assert!(size > 0, "Window size has to be positive");
+        return RollingWindow {
+            size,
+            aggregate,
+            values: VecDeque::with_capacity(size + 1),
+            pushed: 0,
+            sum: 0,
+            extremes: VecDeque::new(),
+            halves: MedianHeaps::new()
+        }
+    }
+
+    // The aggregate of the window ending with `value`, None until the window is full
+    pub fn push(&mut self, value: i64) -> Option<f64> {
+        self.values.push_back(value);
+        match self.aggregate {
+            Aggregate::Sum | Aggregate::Mean => self.sum += value,
+            Aggregate::Min | Aggregate::Max => {
+                let keep_min = self.aggregate == Aggregate::Min;
+                while let Some((_, last)) = self.extremes.back() {
+                    if (keep_min && *last >= value) || (!keep_min && *last <= value) {
+                        self.extremes.pop_back();
+                    } else {
+                        break
+                    }
+                }
+                self.extremes.push_back((self.pushed, value));
+            },
+            Aggregate::Median => self.halves.insert((value, self.pushed), self.pushed.saturating_sub(self.size))
+        }
+        self.pushed += 1;
+
+        if self.values.len() > self.size {
+            let dropped = self.values.pop_front().unwrap();
+            match self.aggregate {
+                Aggregate::Sum | Aggregate::Mean => self.sum -= dropped,
+                Aggregate::Min | Aggregate::Max => {
+                    if self.extremes.front().map(|(pos, _)| *pos + self.size < self.pushed).unwrap_or(false) {
+                        self.extremes.pop_front();
+                    }
+                },
+                Aggregate::Median => self.halves.remove((dropped, self.pushed - self.size - 1), self.pushed - self.size)
+            }
+        }
+        if self.values.len() < self.size {
+            return None
+        }
+        return Some(self.current())
+    }
+
+    fn current(&self) -> f64 {
+        return match self.aggregate {
+            Aggregate::Sum => self.sum as f64,
+            Aggregate::Mean => self.sum as f64 / self.size as f64,
+            Aggregate::Min | Aggregate::Max => self.extremes.front().unwrap().1 as f64,
+            Aggregate::Median => self.halves.median()
+        }
+    }
+}
+
+// Aggregates of every full window, in order
+pub fn rolling<I>(values: I, size: usize, aggregate: Aggregate) -> impl Iterator<Item=f64>
+    where I: IntoIterator, I::Item: Into<i64> {
+    let mut window = RollingWindow::new(size, aggregate);
+    return values.into_iter().filter_map(move |value| window.push(value.into()))
+}
+
+// How each window aggregate compares to the previous one. The longest increasing
+// run is the largest number of increases in a row.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct WindowComparison {
+    pub increases: usize,
+    pub decreases: usize,
+    pub unchanged: usize,
+    pub longest_increasing_run: usize
+}
+
+pub fn compare_windows<I>(values: I, size: usize, aggregate: Aggregate) -> WindowComparison
+    where I: IntoIterator, I::Item: Into<i64> {
+    let mut comparison = WindowComparison::default();
+    let mut run = 0;
+    let mut previous: Option<f64> = None;
+    for current in rolling(values, size, aggregate) {
+        if let Some(previous) = previous {
+            if current > previous {
+                comparison.increases += 1;
+                run += 1;
+                comparison.longest_increasing_run = comparison.longest_increasing_run.max(run);
+            } else {
+                if current < previous { comparison.decreases += 1 } else { comparison.unchanged += 1 }
+                run = 0;
+            }
+        }
+        previous = Some(current);
+    }
+    return comparison
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEPTHS: [i32; 10] = [199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+
+    // Aggregates recomputed from scratch for every window
+    fn naive(values: &[i32], size: usize, aggregate: Aggregate) -> Vec<f64> {
+        return values.windows(size).map(|w| {
+            let mut sorted = w.to_vec();
+            sorted.sort_unstable();
+            let sum: i32 = w.iter().sum();
+            match aggregate {
+                Aggregate::Sum => sum as f64,
+                Aggregate::Mean => sum as f64 / size as f64,
+                Aggregate::Min => sorted[0] as f64,
+                Aggregate::Max => sorted[size - 1] as f64,
+                Aggregate::Median if size % 2 == 1 => sorted[size / 2] as f64,
+                Aggregate::Median => (sorted[size / 2 - 1] + sorted[size / 2]) as f64 / 2.0
+            }
+        }).collect()
+    }
+
+    #[test]
+    fn test_rolling_matches_naive() {
+        let values = [5, 3, 3, 9, -2, 7, 7, 1, 0, 8, 8, 2, 6, -4, 3];
+        let aggregates = [Aggregate::Sum, Aggregate::Mean, Aggregate::Min, Aggregate::Max, Aggregate::Median];
+        for aggregate in aggregates.iter() {
+            for size in 1..=6 {
+                let rolled: Vec<f64> = rolling(values.iter().copied(), size, *aggregate).collect();
+                assert_eq!(rolled, naive(&values, size, *aggregate), "{:?} over {}", aggregate, size);
+            }
+        }
+        assert_eq!(rolling(vec![1u8, 2], 3, Aggregate::Sum).count(), 0);
+
+        // Long runs of equal values, pseudo-random otherwise
+        let values: Vec<i32> = (0..200).map(|i: i32| if i % 17 < 6 { 4 } else { (i * 7919) % 23 - 11 }).collect();
+        let mut window = RollingWindow::new(3, Aggregate::Median);
+        for value in 0..1000 { window.push(value); }
+        assert!(window.halves.low.len() + window.halves.high.len() <= 10);
+        for size in [1, 2, 7, 10, 31].iter() {
+            let rolled: Vec<f64> = rolling(values.iter().copied(), *size, Aggregate::Median).collect();
+            assert_eq!(rolled, naive(&values, *size, Aggregate::Median), "median over {}", size);
+        }
+    }
+
+    #[test]
+    fn test_compare_windows() {
+        assert_eq!(compare_windows(DEPTHS.iter().copied(), 1, Aggregate::Sum), WindowComparison {
+            increases: 7, decreases: 2, unchanged: 0, longest_increasing_run: 3
+        });
+        assert_eq!(compare_windows(DEPTHS.iter().copied(), 3, Aggregate::Sum), WindowComparison {
+            increases: 5, decreases: 1, unchanged: 1, longest_increasing_run: 4
+        });
+        assert_eq!(compare_windows(DEPTHS.iter().copied(), 3, Aggregate::Min), WindowComparison {
+            increases: 4, decreases: 0, unchanged: 3, longest_increasing_run: 3
+        });
+        assert_eq!(compare_windows(Vec::<i32>::new(), 3, Aggregate::Median), WindowComparison::default());
+    }
+
+    #[test]
+    fn test_parse_aggregate() {
+        assert_eq!(Aggregate::parse("median"), Ok(Aggregate::Median));
+        assert!(Aggregate::parse("mode").is_err());
+    }
+}
@@ -1,20 +1,41 @@
-mod exs;
-use exs::day_1_sonar_sweep::day_1;
-use exs::day_2_dive::day_2;
-use exs::day_3_binary_diagnostic::day_3;
-use exs::day_4_giant_squid::day_4;
-use exs::day_5_hydrothermal_venture::day_5;
-use exs::day_6_lanternfish::day_6;
-use exs::day_7_the_threachery_of_whales::day_7;
-use exs::day_8_seven_segment_search::day_8;
-use exs::day_9_smoke_basin::day_9;
-use exs::day_10_syntax_scoring::day_10;
-use exs::day_11_dumbo_octopus::day_11;
-use exs::day_12_passage_pathing::day_12;
-use exs::day_13_transparent_origami::day_13;
-use exs::day_14_extended_polymerization::day_14;
+use std::env;
+
+use rust::exs::day_1_sonar_sweep::{day_1, sonar_windows_command};
+use rust::exs::day_2_dive::{course_command, course_trajectory_command, day_2};
+use rust::exs::day_3_binary_diagnostic::day_3;
+use rust::exs::day_4_giant_squid::day_4;
+use rust::exs::day_5_hydrothermal_venture::day_5;
+use rust::exs::day_6_lanternfish::day_6;
+use rust::exs::day_7_the_threachery_of_whales::day_7;
+use rust::exs::day_8_seven_segment_search::day_8;
+use rust::exs::day_9_smoke_basin::{day_9, smoke_distances_command};
+use rust::exs::day_10_syntax_scoring::day_10;
+use rust::exs::day_11_dumbo_octopus::{day_11, octopus_command};
+use rust::exs::day_12_passage_pathing::{caves_command, caves_dot_command, caves_report_command, day_12};
+use rust::exs::day_13_transparent_origami::day_13;
+use rust::exs::day_14_extended_polymerization::day_14;
+use rust::exs::day_15_chiton::{chiton_command, day_15};
+use rust::exs::sonar_stats::sonar_stats_command;
 
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(|a| a.as_str()) {
+        None => run_all_days(),
+        Some("chiton") => chiton_command(&args[1..]),
+        Some("course") => course_command(&args[1..]),
+        Some("course-trajectory") => course_trajectory_command(&args[1..]),
+        Some("caves") => caves_command(&args[1..]),
+        Some("caves-dot") => caves_dot_command(&args[1..]),
+        Some("caves-report") => caves_report_command(&args[1..]),
+        Some("octopus") => octopus_command(&args[1..]),
+        Some("smoke-distances") => smoke_distances_command(&args[1..]),
+        Some("sonar-stats") => sonar_stats_command(&args[1..]),
+        Some("sonar-windows") => sonar_windows_command(&args[1..]),
+        Some(command) => panic!("Unknown command {}", command)
+    }
+}
+
+fn run_all_days() {
     day_1();
     day_2();
     day_3();
@@ -28,5 +49,6 @@ fn main() {
     day_11();
     day_12();
     day_13();
-    day_14()
+    day_14();
+    day_15()
 }